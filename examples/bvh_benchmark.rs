@@ -0,0 +1,83 @@
+//! Renders grids of spheres of a few different sizes twice each: once with the bounding volume
+//! hierarchy skipping distant spheres, and once with it turned off so every sphere is checked at
+//! every step. Scenes with only a handful of objects check all of them either way, so the
+//! smallest grid shouldn't show any difference.
+
+use raymarch_scratchpad::*;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Each scene is a square grid of spheres with this many spheres along each side.
+const GRID_SIZES: [i32; 4] = [2, 5, 10, 20];
+
+fn make_scene(grid_size: i32, use_bvh: bool) -> Scene {
+    let mut scene = Scene::new();
+    scene.set_sky_color((0.3, 0.7, 0.9));
+    scene.add_light(DirectionalLight {
+        direction: Vec3::from((1, 1, 0.5)).normalized(),
+        percent_size: 0.1,
+        color: 1.into(),
+    });
+    for x in 0..grid_size {
+        for z in 0..grid_size {
+            let material = BasicMaterial {
+                base_color: (
+                    x as f32 / grid_size as f32,
+                    0.5,
+                    z as f32 / grid_size as f32,
+                )
+                    .into(),
+                ..Default::default()
+            };
+            let offset = (x * 3 - grid_size * 3 / 2, 2, z * 3 + 5);
+            scene.add_object(sphere(material).translated(offset));
+        }
+    }
+    scene.set_use_bvh(use_bvh);
+    scene
+}
+
+fn time_render(scene: &Scene, name: &str) -> f32 {
+    let renderer = Renderer {
+        camera: Camera {
+            position: (0, -10, -10).into(),
+            look_at: (0, 2, 30).into(),
+            projection: Arc::new(Perspective { fov: 60.0 }),
+            width: 64,
+            height: 64,
+            ..Default::default()
+        },
+        samples: 4,
+        num_bounces: 2,
+        pixel_size: 0.667,
+        threads: 0,
+        tile_size: 16,
+        tracer: Tracer::default(),
+        spectral: false,
+        post_process: AcesFilmicCurve,
+    };
+    let path = std::env::temp_dir().join(name);
+    let start = Instant::now();
+    renderer.render(scene, path.to_str().unwrap());
+    start.elapsed().as_secs_f32()
+}
+
+fn main() {
+    println!(
+        "{:>8} {:>10} {:>10} {:>8}",
+        "objects", "bvh", "no bvh", "speedup"
+    );
+    for &grid_size in &GRID_SIZES {
+        let with_bvh = make_scene(grid_size, true);
+        let without_bvh = make_scene(grid_size, false);
+        let bvh_time = time_render(&with_bvh, "bvh_benchmark_bvh.png");
+        let linear_time = time_render(&without_bvh, "bvh_benchmark_linear.png");
+        println!(
+            "{:>8} {:>9.2}s {:>9.2}s {:>7.1}x",
+            grid_size * grid_size,
+            bvh_time,
+            linear_time,
+            linear_time / bvh_time
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! Sends a ray through every pixel with plain sphere tracing and with the adaptive tracer, then
+//! compares how many steps each one took and whether they hit the same things. The few pixels
+//! that differ are on the edges of objects, where rays skim the surface and the adaptive tracer
+//! counts anything within a fraction of a pixel as a hit. Running the tests checks that this
+//! still holds.
+
+use raymarch_scratchpad::*;
+use std::sync::Arc;
+
+fn make_scene() -> Scene {
+    let mut scene = Scene::new();
+    let material = BasicMaterial::default();
+    scene.add_object(cube(material.clone(), (200, 0.1, 200)).translated((0, 2, 0)));
+    for i in 0..8 {
+        let offset = (i as f32 * 3.0 - 12.0, 0.5, 15.0 + i as f32 * 4.0);
+        scene.add_object(sphere(material.clone()).scaled(1.5).translated(offset));
+    }
+    scene.add_object(
+        torus(material.clone(), 3.0, 0.5)
+            .rotated_around((1, 0, 0), 60.0)
+            .translated((2, -2, 25)),
+    );
+    scene.add_object(
+        cube(material, (0.7, 2.7, 0.7))
+            .rounded(0.3)
+            .translated((-4, -1, 30)),
+    );
+    scene
+}
+
+struct Totals {
+    steps: u64,
+    hits: u32,
+}
+
+fn trace_all(scene: &Scene, camera: &Camera, tracer: &Tracer) -> (Totals, Vec<Option<Hit>>) {
+    let mut totals = Totals { steps: 0, hits: 0 };
+    let mut hits = Vec::new();
+    for y in 0..camera.height {
+        for x in 0..camera.width {
+            let (origin, direction) = camera.primary_ray(x as f32 + 0.5, y as f32 + 0.5).unwrap();
+            let hit = scene.march_until_hit(tracer, origin, direction);
+            if let Some(hit) = hit {
+                totals.steps += hit.steps as u64;
+                totals.hits += 1;
+            }
+            hits.push(hit);
+        }
+    }
+    (totals, hits)
+}
+
+/// How the ray through a single pixel differs between the two tracers.
+enum PixelComparison {
+    BothMissed,
+    /// Both tracers hit the same object, with distances which differ by this fraction.
+    SameObject(f32),
+    Different,
+}
+
+struct Comparison {
+    width: u32,
+    height: u32,
+    plain_totals: Totals,
+    adaptive_totals: Totals,
+    /// What plain sphere tracing hit through each pixel, row by row.
+    plain_objects: Vec<Option<usize>>,
+    /// Row by row, like plain_objects.
+    pixels: Vec<PixelComparison>,
+}
+
+impl Comparison {
+    fn mismatched(&self) -> usize {
+        self.pixels
+            .iter()
+            .filter(|pixel| matches!(pixel, PixelComparison::Different))
+            .count()
+    }
+
+    fn max_error(&self) -> f32 {
+        self.pixels
+            .iter()
+            .map(|pixel| match pixel {
+                PixelComparison::SameObject(error) => *error,
+                _ => 0.0,
+            })
+            .fold(0.0, f32::max)
+    }
+
+    /// Pixels which hit something different even though they aren't on the edge of an object.
+    fn mismatched_away_from_edges(&self) -> Vec<(u32, u32)> {
+        let mut result = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pixel = &self.pixels[(y * self.width + x) as usize];
+                if matches!(pixel, PixelComparison::Different) && !self.on_edge(x, y) {
+                    result.push((x, y));
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether plain sphere tracing hit something different in any of the pixels around this
+    /// one, meaning this pixel is on the edge of an object.
+    fn on_edge(&self, x: u32, y: u32) -> bool {
+        let object = self.plain_objects[(y * self.width + x) as usize];
+        let xs = x.saturating_sub(1)..=(x + 1).min(self.width - 1);
+        xs.flat_map(|nx| {
+            let ys = y.saturating_sub(1)..=(y + 1).min(self.height - 1);
+            ys.map(move |ny| (nx, ny))
+        })
+        .any(|(nx, ny)| self.plain_objects[(ny * self.width + nx) as usize] != object)
+    }
+}
+
+fn compare(width: u32, height: u32) -> Comparison {
+    let scene = make_scene();
+    let camera = Camera {
+        position: (0, -3, 0).into(),
+        look_at: (0, 0, 30).into(),
+        projection: Arc::new(Perspective { fov: 50.0 }),
+        width,
+        height,
+        ..Default::default()
+    };
+    let adaptive = Tracer::adaptive(&camera);
+    let (plain_totals, plain_hits) = trace_all(&scene, &camera, &Tracer::default());
+    let (adaptive_totals, adaptive_hits) = trace_all(&scene, &camera, &adaptive);
+
+    let pixels = plain_hits
+        .iter()
+        .zip(adaptive_hits.iter())
+        .map(|(plain, adaptive)| match (plain, adaptive) {
+            (Some(plain), Some(adaptive)) if plain.object == adaptive.object => {
+                PixelComparison::SameObject(
+                    (plain.distance - adaptive.distance).abs() / plain.distance,
+                )
+            }
+            (None, None) => PixelComparison::BothMissed,
+            _ => PixelComparison::Different,
+        })
+        .collect();
+    Comparison {
+        width,
+        height,
+        plain_totals,
+        adaptive_totals,
+        plain_objects: plain_hits
+            .iter()
+            .map(|hit| hit.map(|hit| hit.object))
+            .collect(),
+        pixels,
+    }
+}
+
+fn main() {
+    let comparison = compare(400, 300);
+    let average = |totals: &Totals| totals.steps as f32 / totals.hits.max(1) as f32;
+    let plain = &comparison.plain_totals;
+    let adaptive = &comparison.adaptive_totals;
+    println!(
+        "Plain:    {} hits, {} steps, {:.1} steps per hit",
+        plain.hits,
+        plain.steps,
+        average(plain)
+    );
+    println!(
+        "Adaptive: {} hits, {} steps, {:.1} steps per hit",
+        adaptive.hits,
+        adaptive.steps,
+        average(adaptive)
+    );
+    println!(
+        "{} of {} pixels hit something different, {} of them away from the edges of objects",
+        comparison.mismatched(),
+        comparison.pixels.len(),
+        comparison.mismatched_away_from_edges().len()
+    );
+    println!(
+        "Where both tracers hit the same object, the distances differ by at most {:.3}%",
+        comparison.max_error() * 100.0
+    );
+}
+
+#[test]
+fn adaptive_tracer_takes_fewer_steps_for_the_same_image() {
+    let comparison = compare(160, 120);
+    assert!(
+        comparison.adaptive_totals.steps < comparison.plain_totals.steps,
+        "adaptive tracer took {} steps, plain sphere tracing took {}",
+        comparison.adaptive_totals.steps,
+        comparison.plain_totals.steps
+    );
+    // Rays skimming the edges of objects can go either way, but that should only be a
+    // handful of pixels.
+    assert!(
+        comparison.mismatched() * 100 <= comparison.pixels.len(),
+        "{} of {} pixels hit something different",
+        comparison.mismatched(),
+        comparison.pixels.len()
+    );
+    let away_from_edges = comparison.mismatched_away_from_edges();
+    assert!(
+        away_from_edges.is_empty(),
+        "pixels {:?} hit something different away from any edge",
+        away_from_edges
+    );
+}
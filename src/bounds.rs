@@ -0,0 +1,199 @@
+use crate::{Mat4, Quaternion, Vec3};
+
+/// An axis aligned box that an object is guaranteed to fit inside of.
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    pub fn new<T: Into<Vec3>, U: Into<Vec3>>(min: T, max: U) -> Self {
+        Self {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    /// A box which contains everything, for objects which go on forever or whose size can't be
+    /// figured out.
+    pub fn infinite() -> Self {
+        Self::new(f32::NEG_INFINITY, f32::INFINITY)
+    }
+
+    /// A box which contains nothing, for objects which have nothing to hit, like the
+    /// intersection of two objects which don't touch.
+    pub fn empty() -> Self {
+        Self::new(f32::INFINITY, f32::NEG_INFINITY)
+    }
+
+    /// A box which is centered on the origin and goes from -half_size to half_size.
+    pub fn centered<T: Into<Vec3>>(half_size: T) -> Self {
+        let half_size = half_size.into();
+        Self::new(half_size * -1, half_size)
+    }
+
+    /// The smallest box containing all the given points.
+    pub fn around_points<I: IntoIterator<Item = Vec3>>(points: I) -> Self {
+        let mut result = Self::empty();
+        for point in points {
+            result.min = result.min.min(point);
+            result.max = result.max.max(point);
+        }
+        result
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        let finite = |v: Vec3| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+        !self.is_empty() && (!finite(self.min) || !finite(self.max))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// The box containing only the space inside both boxes.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let result = Self::new(self.min.max(other.min), self.max.min(other.max));
+        if result.is_empty() {
+            Self::empty()
+        } else {
+            result
+        }
+    }
+
+    /// Grows the box by the given amount in every direction.
+    pub fn expanded(&self, amount: f32) -> Self {
+        Self::new(self.min - amount, self.max + amount)
+    }
+
+    pub fn translated<T: Into<Vec3>>(&self, offset: T) -> Self {
+        let offset = offset.into();
+        Self::new(self.min + offset, self.max + offset)
+    }
+
+    /// Scales the box away from the origin.
+    pub fn scaled<T: Into<Vec3>>(&self, scale: T) -> Self {
+        if self.is_empty() {
+            return Self::empty();
+        }
+        let scale = scale.into();
+        Self::around_points(vec![self.min * scale, self.max * scale])
+    }
+
+    pub fn rotated(&self, rotation: Quaternion) -> Self {
+        if self.is_empty() {
+            return Self::empty();
+        }
+        if self.is_infinite() {
+            return Self::infinite();
+        }
+        Self::around_points(self.corners().iter().map(|&c| rotation.rotate(c)))
+    }
+
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        if self.is_empty() {
+            return Self::empty();
+        }
+        if self.is_infinite() {
+            return Self::infinite();
+        }
+        Self::around_points(self.corners().iter().map(|&c| transform.transform_point(c)))
+    }
+
+    /// The furthest any point in the box is from the origin.
+    pub fn max_distance_from_origin(&self) -> f32 {
+        self.min.abs().max(self.max.abs()).magnitude()
+    }
+
+    /// Returns how far along the ray it enters and leaves the box, or None if it misses. The
+    /// entry distance is negative if the ray starts inside the box.
+    pub fn intersect_ray(&self, origin: Vec3, direction: Vec3) -> Option<(f32, f32)> {
+        if self.is_empty() {
+            return None;
+        }
+        let near = (self.min - origin) / direction;
+        let far = (self.max - origin) / direction;
+        let enter = near.min(far).max_component();
+        let exit = near.max(far);
+        let exit = exit.x.min(exit.y).min(exit.z);
+        if enter <= exit {
+            Some((enter, exit))
+        } else {
+            None
+        }
+    }
+
+    /// How far the point is from the box, or zero if the point is inside it. Empty boxes are
+    /// infinitely far away from everything.
+    pub fn distance_to(&self, point: Vec3) -> f32 {
+        if self.is_empty() {
+            return f32::INFINITY;
+        }
+        let outside = (self.min - point).max(point - self.max).max(0);
+        outside.magnitude()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extents(b: BoundingBox) -> [f32; 6] {
+        [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z]
+    }
+
+    #[test]
+    fn intersection_of_disjoint_boxes_is_empty() {
+        let a = BoundingBox::new(0, 1);
+        let b = BoundingBox::new(2, 3);
+        let empty = a.intersection(&b);
+        assert!(empty.is_empty());
+        assert!(!empty.is_infinite());
+        assert_eq!(empty.distance_to((0.5, 0.5, 0.5).into()), f32::INFINITY);
+        assert!(empty.intersect_ray(0.into(), (1, 1, 1).into()).is_none());
+        // Empty boxes don't add anything to other boxes.
+        let union = empty.union(&a);
+        assert_eq!(extents(union), extents(a));
+        assert!(empty.scaled((-1, 2, 2)).is_empty());
+        let rotation = Quaternion::from_euler((10, 20, 30));
+        assert!(empty.rotated(rotation).is_empty());
+    }
+
+    #[test]
+    fn intersection_of_overlapping_boxes() {
+        let a = BoundingBox::new(0, 2);
+        let b = BoundingBox::new(1, 3);
+        let both = a.intersection(&b);
+        assert!(!both.is_empty());
+        assert_eq!(extents(both), [1.0, 1.0, 1.0, 2.0, 2.0, 2.0]);
+        assert_eq!(both.distance_to((3, 1.5, 1.5).into()), 1.0);
+    }
+}
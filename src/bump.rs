@@ -0,0 +1,41 @@
+use crate::{tangents, ImageTexture, Material, MaterialSample, Texture, Vec3};
+
+/// Tilts the shading normal of a material to follow the slopes of a height texture, as if the
+/// surface had been pushed outwards by the height times strength.
+pub struct Bumped<M: Material, T: Texture> {
+    pub(crate) material: M,
+    pub(crate) height: T,
+    pub(crate) strength: f32,
+}
+
+impl<M: Material, T: Texture> Material for Bumped<M, T> {
+    fn sample(&self, pos: Vec3, normal: Vec3) -> MaterialSample {
+        const EPSILON: f32 = 1e-4;
+        let mut sample = self.material.sample(pos, normal);
+        let base = sample.normal.unwrap_or(normal);
+        let height = |offset: Vec3| self.height.value_at(pos + offset, normal).average();
+        let slope = |direction: Vec3| {
+            (height(direction * EPSILON) - height(direction * -EPSILON)) / (2.0 * EPSILON)
+        };
+        let (tangent, bitangent) = tangents(base);
+        let gradient = tangent * slope(tangent) + bitangent * slope(bitangent);
+        sample.normal = Some((base - gradient * self.strength).normalized());
+        sample
+    }
+}
+
+/// Tilts the shading normal of a material using a tangent space normal map.
+pub struct NormalMapped<M: Material> {
+    pub(crate) material: M,
+    pub(crate) map: ImageTexture,
+    pub(crate) strength: f32,
+}
+
+impl<M: Material> Material for NormalMapped<M> {
+    fn sample(&self, pos: Vec3, normal: Vec3) -> MaterialSample {
+        let mut sample = self.material.sample(pos, normal);
+        let base = sample.normal.unwrap_or(normal);
+        sample.normal = Some(self.map.normal_at(pos, normal, base, self.strength));
+        sample
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{BoundingBox, RenderedObject, Vec3};
+
+/// Checking a handful of objects directly is faster than checking more boxes to narrow them
+/// down, so leaves hold up to this many objects.
+const MAX_LEAF_SIZE: usize = 4;
+
+enum NodeKind {
+    /// Range of indexes into Bvh::leaf_objects.
+    Leaf(usize, usize),
+    /// Indexes of the two child nodes.
+    Branch(usize, usize),
+}
+
+struct Node {
+    bounds: BoundingBox,
+    /// Length of the diagonal of the bounds.
+    diagonal: f32,
+    kind: NodeKind,
+}
+
+/// Bounding volume hierarchy, which groups nearby objects together so that entire groups can be
+/// skipped when they are far away from the point being checked.
+pub(crate) struct Bvh {
+    nodes: Vec<Node>,
+    /// Indexes of objects in the scene, ordered so that the objects in each leaf are next to
+    /// each other.
+    leaf_objects: Vec<usize>,
+    root: Option<usize>,
+    /// Objects which go on forever can't be put in the tree, so they are checked every time.
+    /// When there are only a few objects in the scene, all of them go here instead.
+    always_checked: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build(objects: &[Box<dyn RenderedObject>]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            leaf_objects: Vec::new(),
+            root: None,
+            always_checked: Vec::new(),
+        };
+        let mut bounded = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            let bounds = object.bounds();
+            if bounds.is_empty() {
+                // There's nothing in the object to hit.
+                continue;
+            }
+            if bounds.is_infinite() {
+                bvh.always_checked.push(index);
+            } else {
+                bounded.push((index, bounds));
+            }
+        }
+        if bounded.len() <= MAX_LEAF_SIZE {
+            // The whole tree would be a single leaf, and checking its box would only slow
+            // things down.
+            bvh.always_checked
+                .extend(bounded.iter().map(|(index, _)| index));
+        } else {
+            bvh.root = Some(bvh.build_node(&mut bounded));
+        }
+        bvh
+    }
+
+    /// Skips building a tree and checks every object every time, to compare against.
+    pub fn linear(objects: &[Box<dyn RenderedObject>]) -> Self {
+        Self {
+            nodes: Vec::new(),
+            leaf_objects: Vec::new(),
+            root: None,
+            always_checked: (0..objects.len()).collect(),
+        }
+    }
+
+    /// Recursively builds nodes for the given objects, returning the index of the node
+    /// containing all of them.
+    fn build_node(&mut self, objects: &mut [(usize, BoundingBox)]) -> usize {
+        if objects.len() <= MAX_LEAF_SIZE {
+            let start = self.leaf_objects.len();
+            self.leaf_objects
+                .extend(objects.iter().map(|(index, _)| index));
+            let bounds = objects[1..]
+                .iter()
+                .fold(objects[0].1, |all, (_, b)| all.union(b));
+            self.nodes.push(Node {
+                bounds,
+                diagonal: bounds.size().magnitude(),
+                kind: NodeKind::Leaf(start, self.leaf_objects.len()),
+            });
+            return self.nodes.len() - 1;
+        }
+        let centers = BoundingBox::around_points(objects.iter().map(|(_, b)| b.center()));
+        let spread = centers.size();
+        // Split the objects in half along whichever axis they are most spread out on.
+        let axis_value: fn(Vec3) -> f32 = if spread.x >= spread.y && spread.x >= spread.z {
+            |v| v.x
+        } else if spread.y >= spread.z {
+            |v| v.y
+        } else {
+            |v| v.z
+        };
+        objects.sort_by(|(_, a), (_, b)| {
+            axis_value(a.center())
+                .partial_cmp(&axis_value(b.center()))
+                .unwrap()
+        });
+        let (left, right) = objects.split_at_mut(objects.len() / 2);
+        let left = self.build_node(left);
+        let right = self.build_node(right);
+        let bounds = self.nodes[left].bounds.union(&self.nodes[right].bounds);
+        self.nodes.push(Node {
+            bounds,
+            diagonal: bounds.size().magnitude(),
+            kind: NodeKind::Branch(left, right),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Returns a value which is never larger than the distance to the closest object, and is
+    /// exact when the point is close to an object. Also returns the index of the object the
+    /// distance came from, or None if it came from a bounding box instead of an object.
+    pub fn distance_to(
+        &self,
+        objects: &[Box<dyn RenderedObject>],
+        point: Vec3,
+    ) -> (f32, Option<usize>) {
+        let mut best = (f32::MAX, None);
+        for &index in &self.always_checked {
+            let distance = objects[index].distance_to(point);
+            if distance < best.0 {
+                best = (distance, Some(index));
+            }
+        }
+        if let Some(root) = self.root {
+            let box_distance = self.nodes[root].bounds.distance_to(point);
+            self.distance_in_node(objects, point, root, box_distance, &mut best);
+        }
+        best
+    }
+
+    fn distance_in_node(
+        &self,
+        objects: &[Box<dyn RenderedObject>],
+        point: Vec3,
+        node: usize,
+        box_distance: f32,
+        best: &mut (f32, Option<usize>),
+    ) {
+        if box_distance >= best.0 {
+            return;
+        }
+        let node = &self.nodes[node];
+        // When the point is far away from the box compared to how big the box is, the distance
+        // to the box is close enough to the distance to whatever is inside it.
+        if box_distance > node.diagonal {
+            *best = (box_distance, None);
+            return;
+        }
+        match node.kind {
+            NodeKind::Leaf(start, end) => {
+                for &index in &self.leaf_objects[start..end] {
+                    let distance = objects[index].distance_to(point);
+                    if distance < best.0 {
+                        *best = (distance, Some(index));
+                    }
+                }
+            }
+            NodeKind::Branch(left, right) => {
+                let ((near, near_distance), (far, far_distance)) =
+                    self.order_children(left, right, point);
+                self.distance_in_node(objects, point, near, near_distance, best);
+                self.distance_in_node(objects, point, far, far_distance, best);
+            }
+        }
+    }
+
+    /// Returns the index of the object closest to the point along with its distance.
+    pub fn closest(
+        &self,
+        objects: &[Box<dyn RenderedObject>],
+        point: Vec3,
+    ) -> Option<(usize, f32)> {
+        let mut best: Option<(usize, f32)> = None;
+        for &index in &self.always_checked {
+            let distance = objects[index].distance_to(point);
+            if best.map(|(_, d)| distance < d).unwrap_or(true) {
+                best = Some((index, distance));
+            }
+        }
+        if let Some(root) = self.root {
+            self.closest_in_node(objects, point, root, &mut best);
+        }
+        best
+    }
+
+    fn closest_in_node(
+        &self,
+        objects: &[Box<dyn RenderedObject>],
+        point: Vec3,
+        node: usize,
+        best: &mut Option<(usize, f32)>,
+    ) {
+        let node = &self.nodes[node];
+        if let Some((_, best_distance)) = *best {
+            if node.bounds.distance_to(point) >= best_distance {
+                return;
+            }
+        }
+        match node.kind {
+            NodeKind::Leaf(start, end) => {
+                for &index in &self.leaf_objects[start..end] {
+                    let distance = objects[index].distance_to(point);
+                    if best.map(|(_, d)| distance < d).unwrap_or(true) {
+                        *best = Some((index, distance));
+                    }
+                }
+            }
+            NodeKind::Branch(left, right) => {
+                let ((near, _), (far, _)) = self.order_children(left, right, point);
+                self.closest_in_node(objects, point, near, best);
+                self.closest_in_node(objects, point, far, best);
+            }
+        }
+    }
+
+    /// Returns the children along with the distances to their boxes, closest first. Checking
+    /// the closer child first finds close objects sooner, which lets more of the tree be
+    /// skipped.
+    fn order_children(
+        &self,
+        left: usize,
+        right: usize,
+        point: Vec3,
+    ) -> ((usize, f32), (usize, f32)) {
+        let left = (left, self.nodes[left].bounds.distance_to(point));
+        let right = (right, self.nodes[right].bounds.distance_to(point));
+        if left.1 <= right.1 {
+            (left, right)
+        } else {
+            (right, left)
+        }
+    }
+}
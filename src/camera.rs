@@ -0,0 +1,132 @@
+use crate::{Perspective, Projection, Vec3};
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct Camera {
+    pub position: Vec3,
+    /// The point the camera is pointed at.
+    pub look_at: Vec3,
+    /// Which direction is up in the final image. This does not have to be perpendicular to the
+    /// viewing direction, it just can't be parallel to it.
+    pub up: Vec3,
+    pub projection: Arc<dyn Projection>,
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// Radius of the lens. Zero makes a pinhole camera where everything is in focus, bigger
+    /// values make things away from the focal plane blurrier.
+    pub aperture_radius: f32,
+    /// How far in front of the camera things are perfectly in focus.
+    pub focal_distance: f32,
+    /// How many blades the aperture has, which controls the shape of out of focus highlights.
+    /// Anything less than three makes a perfectly circular aperture.
+    pub aperture_blades: u32,
+    /// Rotation of the aperture blades in degrees.
+    pub aperture_rotation: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            position: 0.into(),
+            look_at: (0, 0, 1).into(),
+            up: (0, -1, 0).into(),
+            projection: Arc::new(Perspective { fov: 35.0 }),
+            width: 100,
+            height: 100,
+            aperture_radius: 0.0,
+            focal_distance: 1.0,
+            aperture_blades: 0,
+            aperture_rotation: 0.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Returns the forward, right, and up vectors of the camera, all normalized and perpendicular
+    /// to each other.
+    pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let forward = (self.look_at - self.position).normalized();
+        let right = forward.cross(self.up).normalized();
+        let up = right.cross(forward);
+        (forward, right, up)
+    }
+
+    /// Returns the origin and direction of the ray passing through the given position on the
+    /// image. The position is measured in pixels, so (0, 0) is the top left corner of the image
+    /// and (width, height) is the bottom right corner. Returns None if the projection does not
+    /// cover that part of the image.
+    pub fn primary_ray(&self, x: f32, y: f32) -> Option<(Vec3, Vec3)> {
+        let screen_x = (x / self.width as f32 - 0.5) * 2.0;
+        let screen_y = (0.5 - y / self.height as f32) * 2.0;
+        let (mut origin, mut direction) =
+            self.projection
+                .project(screen_x, screen_y, self.aspect_ratio())?;
+        if self.aperture_radius > 0.0 {
+            // Rays pointing forward focus on a plane, anything else focuses on a sphere around
+            // the camera.
+            let focal_distance = if direction.z > 0.0 {
+                self.focal_distance / direction.z
+            } else {
+                self.focal_distance / direction.magnitude()
+            };
+            let focus_point = origin + direction * focal_distance;
+            let (lens_x, lens_y) = self.sample_aperture();
+            origin += (lens_x, lens_y, 0);
+            direction = focus_point - origin;
+        }
+        let (forward, right, up) = self.basis();
+        let to_world = |v: Vec3| right * v.x + up * v.y + forward * v.z;
+        Some((
+            self.position + to_world(origin),
+            to_world(direction).normalized(),
+        ))
+    }
+
+    /// Returns how wide a pixel in the middle of the image is where rays start, and how much
+    /// wider it gets for every unit of distance away from the camera. Depth of field is ignored.
+    pub fn pixel_footprint(&self) -> (f32, f32) {
+        let aspect_ratio = self.aspect_ratio();
+        let pixel = 2.0 / self.height as f32;
+        let center = self.projection.project(0.0, 0.0, aspect_ratio);
+        let next = self.projection.project(0.0, pixel, aspect_ratio);
+        match (center, next) {
+            (Some((origin_a, dir_a)), Some((origin_b, dir_b))) => (
+                (origin_b - origin_a).magnitude(),
+                (dir_b.normalized() - dir_a.normalized()).magnitude(),
+            ),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Returns a uniformly distributed random point on the aperture.
+    fn sample_aperture(&self) -> (f32, f32) {
+        let mut rng = rand::thread_rng();
+        if self.aperture_blades < 3 {
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            let radius = self.aperture_radius * rng.gen_range(0.0f32, 1.0).sqrt();
+            return (radius * angle.cos(), radius * angle.sin());
+        }
+        // The aperture is a regular polygon, pick one of the triangles between the center and
+        // two neighboring corners (they all have the same area) then pick a point inside it.
+        let blade_angle = 2.0 * PI / self.aperture_blades as f32;
+        let blade = rng.gen_range(0, self.aperture_blades) as f32;
+        let start_angle = self.aperture_rotation.to_radians() + blade * blade_angle;
+        let end_angle = start_angle + blade_angle;
+        let (mut a, mut b) = (rng.gen_range(0.0f32, 1.0), rng.gen_range(0.0f32, 1.0));
+        if a + b > 1.0 {
+            a = 1.0 - a;
+            b = 1.0 - b;
+        }
+        let x = a * start_angle.cos() + b * end_angle.cos();
+        let y = a * start_angle.sin() + b * end_angle.sin();
+        (x * self.aperture_radius, y * self.aperture_radius)
+    }
+}
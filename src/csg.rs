@@ -0,0 +1,242 @@
+use crate::{BoundingBox, MaterialSample, RenderedObject, Vec3};
+
+/// Everything inside either object.
+pub struct Union<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for Union<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.a.distance_to(point).min(self.b.distance_to(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        if self.a.distance_to(point) <= self.b.distance_to(point) {
+            self.a.material_at(point, normal)
+        } else {
+            self.b.material_at(point, normal)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.a.bounds().union(&self.b.bounds())
+    }
+}
+
+/// Everything inside both objects.
+pub struct Intersection<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for Intersection<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.a.distance_to(point).max(self.b.distance_to(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        if self.a.distance_to(point) >= self.b.distance_to(point) {
+            self.a.material_at(point, normal)
+        } else {
+            self.b.material_at(point, normal)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.a.bounds().intersection(&self.b.bounds())
+    }
+}
+
+/// Everything inside the first object but not inside the second. Surfaces carved out by the
+/// second object use its material.
+pub struct Subtraction<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for Subtraction<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.a.distance_to(point).max(-self.b.distance_to(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        if self.a.distance_to(point) >= -self.b.distance_to(point) {
+            self.a.material_at(point, normal)
+        } else {
+            self.b.material_at(point, normal)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.a.bounds()
+    }
+}
+
+/// Everything inside exactly one of the two objects.
+pub struct Xor<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for Xor<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        a.min(b).max(-a.max(b))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        // Outside of the overlap the closest object defines the surface, inside it the
+        // furthest one does.
+        let use_a = if a.min(b) >= -a.max(b) {
+            a <= b
+        } else {
+            a >= b
+        };
+        if use_a {
+            self.a.material_at(point, normal)
+        } else {
+            self.b.material_at(point, normal)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.a.bounds().union(&self.b.bounds())
+    }
+}
+
+/// Controls how two objects are smoothly blended together.
+#[derive(Clone, Copy, Debug)]
+pub enum Blend {
+    /// Blends over exactly the given radius, objects further apart than that are unaffected.
+    Polynomial(f32),
+    /// Blends more gradually and never completely stops, the given radius controls how fast it
+    /// falls off.
+    Exponential(f32),
+}
+
+impl Blend {
+    /// Returns a smoothed version of a.min(b), along with how much of the result comes from b
+    /// (0 when it is entirely a, 1 when it is entirely b.)
+    pub fn min(self, a: f32, b: f32) -> (f32, f32) {
+        match self {
+            Blend::Polynomial(radius) => {
+                if radius <= 0.0 {
+                    return (a.min(b), if a <= b { 0.0 } else { 1.0 });
+                }
+                let h = (0.5 + 0.5 * (b - a) / radius).clamp(0.0, 1.0);
+                (b + (a - b) * h - radius * h * (1.0 - h), 1.0 - h)
+            }
+            Blend::Exponential(radius) => {
+                if radius <= 0.0 {
+                    return (a.min(b), if a <= b { 0.0 } else { 1.0 });
+                }
+                // Subtract the minimum first so the exponents can't overflow.
+                let min = a.min(b);
+                let ea = (-(a - min) / radius).exp();
+                let eb = (-(b - min) / radius).exp();
+                (min - radius * (ea + eb).ln(), eb / (ea + eb))
+            }
+        }
+    }
+
+    /// Returns a smoothed version of a.max(b), along with how much of the result comes from b.
+    pub fn max(self, a: f32, b: f32) -> (f32, f32) {
+        let (value, factor) = self.min(-a, -b);
+        (-value, factor)
+    }
+}
+
+fn blended_material<A: RenderedObject, B: RenderedObject>(
+    a: &A,
+    b: &B,
+    point: Vec3,
+    normal: Vec3,
+    factor: f32,
+) -> MaterialSample {
+    if factor <= 0.0 {
+        a.material_at(point, normal)
+    } else if factor >= 1.0 {
+        b.material_at(point, normal)
+    } else {
+        a.material_at(point, normal)
+            .mix(b.material_at(point, normal), factor)
+    }
+}
+
+pub struct SmoothUnion<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+    pub(crate) blend: Blend,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for SmoothUnion<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        self.blend.min(a, b).0
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        let factor = self.blend.min(a, b).1;
+        blended_material(&self.a, &self.b, point, normal, factor)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        // Blending can make the surface bulge out a bit where the two objects meet.
+        let radius = match self.blend {
+            Blend::Polynomial(radius) | Blend::Exponential(radius) => radius,
+        };
+        self.a
+            .bounds()
+            .union(&self.b.bounds())
+            .expanded(radius.max(0.0))
+    }
+}
+
+pub struct SmoothIntersection<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+    pub(crate) blend: Blend,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for SmoothIntersection<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        self.blend.max(a, b).0
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        let factor = self.blend.max(a, b).1;
+        blended_material(&self.a, &self.b, point, normal, factor)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.a.bounds().intersection(&self.b.bounds())
+    }
+}
+
+pub struct SmoothSubtraction<A: RenderedObject, B: RenderedObject> {
+    pub(crate) a: A,
+    pub(crate) b: B,
+    pub(crate) blend: Blend,
+}
+
+impl<A: RenderedObject, B: RenderedObject> RenderedObject for SmoothSubtraction<A, B> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        self.blend.max(a, -b).0
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (a, b) = (self.a.distance_to(point), self.b.distance_to(point));
+        let factor = self.blend.max(a, -b).1;
+        blended_material(&self.a, &self.b, point, normal, factor)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.a.bounds()
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{BoundingBox, MaterialSample, Quaternion, RenderedObject, Vec3};
+
+/// Deformations stretch space unevenly, which makes the distance field overestimate how far
+/// away the surface is. This multiplies the distance by a factor between 0 and 1 so that rays
+/// take smaller steps and don't march through the surface.
+pub struct SafetyFactor<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) factor: f32,
+}
+
+impl<T: RenderedObject> RenderedObject for SafetyFactor<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(point) * self.factor
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds()
+    }
+}
+
+/// Rotates each slice of the object around an axis by an amount proportional to how far along
+/// the axis it is.
+pub struct Twisted<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) axis: Vec3,
+    pub(crate) degrees_per_unit: f32,
+}
+
+impl<T: RenderedObject> Twisted<T> {
+    /// Returns the rotation which undoes the twist at the given point.
+    fn untwist(&self, point: Vec3) -> Quaternion {
+        let angle = -self.degrees_per_unit * point.dot(self.axis);
+        Quaternion::from_axis_angle(self.axis, angle)
+    }
+
+    fn undeform(&self, point: Vec3) -> Vec3 {
+        self.untwist(point).rotate(point)
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for Twisted<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.undeform(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let rotation = self.untwist(point);
+        self.object
+            .material_at(rotation.rotate(point), rotation.rotate(normal))
+            .map_normal(|normal| rotation.conjugate().rotate(normal))
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.object.bounds().max_distance_from_origin())
+    }
+}
+
+/// Bends the object around the Z axis, so that things further along X get rotated more.
+pub struct Bent<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) degrees_per_unit: f32,
+}
+
+impl<T: RenderedObject> Bent<T> {
+    /// Returns the rotation which undoes the bend at the given point.
+    fn unbend(&self, point: Vec3) -> Quaternion {
+        let angle = -self.degrees_per_unit * point.x;
+        Quaternion::from_axis_angle((0, 0, 1), angle)
+    }
+
+    fn undeform(&self, point: Vec3) -> Vec3 {
+        self.unbend(point).rotate(point)
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for Bent<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.undeform(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let rotation = self.unbend(point);
+        self.object
+            .material_at(rotation.rotate(point), rotation.rotate(normal))
+            .map_normal(|normal| rotation.conjugate().rotate(normal))
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.object.bounds().max_distance_from_origin())
+    }
+}
+
+/// Scales the object perpendicular to an axis by an amount that changes along the axis.
+pub struct Tapered<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) axis: Vec3,
+    /// How much the scale changes per unit along the axis. The scale is 1 where the axis passes
+    /// through the origin.
+    pub(crate) rate: f32,
+}
+
+impl<T: RenderedObject> Tapered<T> {
+    /// Returns the undeformed point along with how much it was scaled by.
+    fn undeform(&self, point: Vec3) -> (Vec3, f32) {
+        let along = point.dot(self.axis);
+        // Don't let the object shrink to nothing or flip inside out.
+        let scale = (1.0 + self.rate * along).max(1e-3);
+        let across = point - self.axis * along;
+        (self.axis * along + across / scale, scale)
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for Tapered<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let (point, scale) = self.undeform(point);
+        self.object.distance_to(point) * scale.min(1.0)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (point, scale) = self.undeform(point);
+        // Squashing the object across the axis tilts its normals towards the axis.
+        let along = self.axis * normal.dot(self.axis);
+        let normal = (along + (normal - along) * scale).normalized();
+        self.object.material_at(point, normal).map_normal(|normal| {
+            let along = self.axis * normal.dot(self.axis);
+            along + (normal - along) / scale
+        })
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let radius = self.object.bounds().max_distance_from_origin();
+        BoundingBox::centered(radius * (1.0 + self.rate.abs() * radius))
+    }
+}
+
+/// Cuts the object in half along each axis and pulls the halves apart, filling in the gap by
+/// stretching whatever was at the cut.
+pub struct Elongated<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) amount: Vec3,
+}
+
+impl<T: RenderedObject> Elongated<T> {
+    fn undeform(&self, point: Vec3) -> Vec3 {
+        point - point.max(self.amount * -1).min(self.amount)
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for Elongated<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.undeform(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(self.undeform(point), normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let bounds = self.object.bounds();
+        BoundingBox::new(bounds.min - self.amount, bounds.max + self.amount)
+    }
+}
+
+/// Pushes the surface of the object outwards by the value of a function. Negative values push
+/// it inwards.
+pub struct Displaced<T: RenderedObject, F: Fn(Vec3) -> f32 + Send + Sync> {
+    pub(crate) object: T,
+    pub(crate) displacement: F,
+}
+
+impl<T: RenderedObject, F: Fn(Vec3) -> f32 + Send + Sync> RenderedObject for Displaced<T, F> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(point) - (self.displacement)(point)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(point, normal)
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{BoundingBox, MaterialSample, Quaternion, RenderedObject, Vec3};
+
+/// Lets a repeated object change its material depending on which copy is being looked at. The
+/// second argument is the index of the copy.
+pub type CellMaterial = Box<dyn Fn(MaterialSample, Vec3) -> MaterialSample + Send + Sync>;
+
+/// Copies an object over and over in a grid. The object should fit inside a single cell of the
+/// grid, otherwise the distance field will have gaps in it.
+pub struct Repeated<T: RenderedObject> {
+    pub(crate) object: T,
+    /// Size of each cell in the grid. Axes with a spacing of zero are not repeated along.
+    pub(crate) spacing: Vec3,
+    /// How many copies to make along each axis, starting at the origin and going towards +X, +Y
+    /// and +Z. None repeats forever in both directions.
+    pub(crate) count: Option<Vec3>,
+    pub(crate) cell_material: Option<CellMaterial>,
+}
+
+impl<T: RenderedObject> Repeated<T> {
+    /// Changes the material of each copy using a function of the material sample and the index
+    /// of the copy in the grid.
+    pub fn with_cell_material<F>(mut self, cell_material: F) -> Self
+    where
+        F: Fn(MaterialSample, Vec3) -> MaterialSample + Send + Sync + 'static,
+    {
+        self.cell_material = Some(Box::new(cell_material));
+        self
+    }
+
+    fn cell_component(&self, pos: f32, spacing: f32, count: Option<f32>) -> f32 {
+        if spacing <= 0.0 {
+            return 0.0;
+        }
+        let cell = (pos / spacing).round();
+        match count {
+            Some(count) => cell.clamp(0.0, (count - 1.0).max(0.0)),
+            None => cell,
+        }
+    }
+
+    /// Returns the index of the cell the point is in along with the position of the point
+    /// relative to the center of that cell.
+    fn fold(&self, point: Vec3) -> (Vec3, Vec3) {
+        let count = self.count;
+        let cell = Vec3::new(
+            self.cell_component(point.x, self.spacing.x, count.map(|c| c.x)),
+            self.cell_component(point.y, self.spacing.y, count.map(|c| c.y)),
+            self.cell_component(point.z, self.spacing.z, count.map(|c| c.z)),
+        );
+        (cell, point - cell * self.spacing)
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for Repeated<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.fold(point).1)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (cell, local) = self.fold(point);
+        let sample = self.object.material_at(local, normal);
+        match &self.cell_material {
+            Some(cell_material) => cell_material(sample, cell),
+            None => sample,
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let count = match self.count {
+            Some(count) => count,
+            None if self.spacing.max_component() > 0.0 => return BoundingBox::infinite(),
+            None => return self.object.bounds(),
+        };
+        let bounds = self.object.bounds();
+        let last_copy = (count - 1).max(0) * self.spacing;
+        BoundingBox::new(bounds.min, bounds.max + last_copy)
+    }
+}
+
+/// Copies an object in a circle around an axis through the origin. The object should fit
+/// inside a single slice of the circle, otherwise the distance field will have gaps in it.
+pub struct RepeatedAround<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) axis: Vec3,
+    /// Together with the axis these describe the plane the copies are arranged in, with the
+    /// original copy sitting towards reference_x.
+    pub(crate) reference_x: Vec3,
+    pub(crate) reference_y: Vec3,
+    pub(crate) count: u32,
+    pub(crate) cell_material: Option<CellMaterial>,
+}
+
+impl<T: RenderedObject> RepeatedAround<T> {
+    /// Changes the material of each copy using a function of the material sample and the index
+    /// of the copy, which is stored in the X component.
+    pub fn with_cell_material<F>(mut self, cell_material: F) -> Self
+    where
+        F: Fn(MaterialSample, Vec3) -> MaterialSample + Send + Sync + 'static,
+    {
+        self.cell_material = Some(Box::new(cell_material));
+        self
+    }
+
+    /// Returns the index of the copy the point is closest to along with the rotation which moves
+    /// it back to where the original copy is.
+    fn fold(&self, point: Vec3) -> (u32, Quaternion) {
+        let slice_degrees = 360.0 / self.count.max(1) as f32;
+        let angle = point
+            .dot(self.reference_y)
+            .atan2(point.dot(self.reference_x))
+            .to_degrees();
+        let cell = (angle / slice_degrees).round();
+        let rotation = Quaternion::from_axis_angle(self.axis, -cell * slice_degrees);
+        let index = (cell as i64).rem_euclid(self.count.max(1) as i64) as u32;
+        (index, rotation)
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for RepeatedAround<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.fold(point).1.rotate(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        let (index, rotation) = self.fold(point);
+        let sample = self
+            .object
+            .material_at(rotation.rotate(point), rotation.rotate(normal))
+            .map_normal(|normal| rotation.conjugate().rotate(normal));
+        match &self.cell_material {
+            Some(cell_material) => cell_material(sample, (index, 0, 0).into()),
+            None => sample,
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.object.bounds().max_distance_from_origin())
+    }
+}
+
+/// Reflects the object across a plane through the origin, so there is a copy of whatever is on
+/// the positive side of the plane on the negative side as well.
+pub struct Mirrored<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) normal: Vec3,
+}
+
+impl<T: RenderedObject> Mirrored<T> {
+    fn reflect(&self, v: Vec3) -> Vec3 {
+        v - self.normal * 2.0 * v.dot(self.normal)
+    }
+
+    fn fold(&self, point: Vec3) -> Vec3 {
+        if point.dot(self.normal) < 0.0 {
+            self.reflect(point)
+        } else {
+            point
+        }
+    }
+}
+
+impl<T: RenderedObject> RenderedObject for Mirrored<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.fold(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        if point.dot(self.normal) < 0.0 {
+            self.object
+                .material_at(self.reflect(point), self.reflect(normal))
+                .map_normal(|normal| self.reflect(normal))
+        } else {
+            self.object.material_at(point, normal)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let bounds = self.object.bounds();
+        if bounds.is_infinite() || bounds.is_empty() {
+            return bounds;
+        }
+        let reflected = bounds
+            .corners()
+            .iter()
+            .map(|&c| self.reflect(c))
+            .collect::<Vec<_>>();
+        bounds.union(&BoundingBox::around_points(reflected))
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{Texture, Vec3};
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{ImageError, ImageResult, RgbImage};
+use std::f32::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How an image gets wrapped around a surface.
+#[derive(Clone, Copy, Debug)]
+pub enum TextureMapping {
+    /// Projects the image along each axis and blends between the three projections depending on
+    /// which way the surface faces, which works on any shape. One copy of the image covers a
+    /// square scale units across. Higher sharpness makes the blends between projections
+    /// narrower, 4 is a good default.
+    Triplanar { scale: f32, sharpness: f32 },
+    /// Wraps the image around the origin like a map around a globe, with the top of the image at
+    /// the top of the globe.
+    Spherical,
+    /// Wraps the image around the Y axis like a label around a can, repeating every height units
+    /// along the axis.
+    Cylindrical { height: f32 },
+}
+
+/// What happens when looking up a part of the image past its edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    /// Tiles the image forever.
+    Repeat,
+    /// Stretches the pixels along the edges outwards forever.
+    Clamp,
+}
+
+/// A texture which shows an image, blending between neighboring pixels so that it doesn't look
+/// blocky up close. Cloning it doesn't copy the image, so the same image can be used for
+/// multiple materials cheaply.
+#[derive(Clone)]
+pub struct ImageTexture {
+    /// Linear colors in row-major order, starting from the top left.
+    pixels: Arc<Vec<Vec3>>,
+    width: u32,
+    height: u32,
+    mapping: TextureMapping,
+    wrap: Wrap,
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl ImageTexture {
+    /// Loads an image file storing colors in sRGB, which is how almost all photographs and color
+    /// textures are stored.
+    pub fn open<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        Self::from_image(&image::open(path)?.into_rgb8(), true)
+    }
+
+    /// Loads an image file storing plain numbers rather than colors, like a roughness map.
+    pub fn open_linear<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        Self::from_image(&image::open(path)?.into_rgb8(), false)
+    }
+
+    /// Uses an image which has already been loaded. srgb should be true if the image stores
+    /// colors in sRGB and false if it stores values linearly. Returns an error if the image is
+    /// empty, since there would be nothing to show.
+    pub fn from_image(image: &RgbImage, srgb: bool) -> ImageResult<Self> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+        let decode = |value: u8| {
+            let value = value as f32 / 255.0;
+            if srgb {
+                srgb_to_linear(value)
+            } else {
+                value
+            }
+        };
+        let pixels = image
+            .pixels()
+            .map(|p| Vec3::new(decode(p[0]), decode(p[1]), decode(p[2])))
+            .collect();
+        Ok(Self {
+            pixels: Arc::new(pixels),
+            width: image.width(),
+            height: image.height(),
+            mapping: TextureMapping::Triplanar {
+                scale: 1.0,
+                sharpness: 4.0,
+            },
+            wrap: Wrap::Repeat,
+        })
+    }
+
+    pub fn with_mapping(mut self, mapping: TextureMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Vec3 {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let (x, y) = match self.wrap {
+            Wrap::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+            Wrap::Clamp => (x.clamp(0, width - 1), y.clamp(0, height - 1)),
+        };
+        self.pixels[(y * width + x) as usize]
+    }
+
+    /// Looks up the image with bilinear filtering, where (0, 0) is the top left corner of the
+    /// image and (1, 1) is the bottom right corner.
+    pub fn sample_uv(&self, u: f32, v: f32) -> Vec3 {
+        // Pixel centers are half a pixel in from the edges.
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self.pixel(x0, y0) * (1.0 - tx) + self.pixel(x0 + 1, y0) * tx;
+        let bottom = self.pixel(x0, y0 + 1) * (1.0 - tx) + self.pixel(x0 + 1, y0 + 1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    /// How far around the Y axis the point is, from 0 to 1, along with the direction which
+    /// goes further around.
+    fn longitude(pos: Vec3) -> (f32, Vec3) {
+        let around =
+            Vec3::new(-pos.z, 0.0, pos.x) / (pos.x * pos.x + pos.z * pos.z).sqrt().max(1e-6);
+        (pos.z.atan2(pos.x) / (2.0 * PI) + 0.5, around)
+    }
+
+    /// Works out where a point on the surface lands on the image. Triplanar mapping lands in
+    /// three places which get blended together, so this calls visit once for each of them.
+    fn map_point<F: FnMut(MappedPoint)>(&self, pos: Vec3, normal: Vec3, mut visit: F) {
+        // Y points down, so V increasing down the image goes along with it.
+        let up = Vec3::from((0, -1, 0));
+        match self.mapping {
+            TextureMapping::Triplanar { scale, sharpness } => {
+                let pos = pos / scale;
+                let weight = |n: f32| n.abs().powf(sharpness);
+                let weights = Vec3::new(weight(normal.x), weight(normal.y), weight(normal.z));
+                let weights = weights / weights.sum().max(1e-6);
+                let projections = [
+                    (weights.x, pos.z, pos.y, (0, 0, 1).into(), up),
+                    (weights.y, pos.x, pos.z, (1, 0, 0).into(), (0, 0, -1).into()),
+                    (weights.z, pos.x, pos.y, (1, 0, 0).into(), up),
+                ];
+                for &(weight, u, v, right, up) in &projections {
+                    if weight > 0.0 {
+                        visit(MappedPoint {
+                            weight,
+                            u,
+                            v,
+                            right,
+                            up,
+                        });
+                    }
+                }
+            }
+            TextureMapping::Spherical => {
+                let distance = pos.magnitude().max(1e-6);
+                let (u, right) = Self::longitude(pos);
+                visit(MappedPoint {
+                    weight: 1.0,
+                    u,
+                    v: (-pos.y / distance).clamp(-1.0, 1.0).acos() / PI,
+                    right,
+                    up,
+                });
+            }
+            TextureMapping::Cylindrical { height } => {
+                let (u, right) = Self::longitude(pos);
+                visit(MappedPoint {
+                    weight: 1.0,
+                    u,
+                    v: pos.y / height,
+                    right,
+                    up,
+                });
+            }
+        }
+    }
+
+    /// Treats the image as a tangent space normal map, using the OpenGL convention where green
+    /// points towards the top of the image, and returns the normal it describes. normal is the
+    /// surface normal used to pick where to look up the image and base is the normal to tilt.
+    /// Strength multiplies how steep the bumps are.
+    pub(crate) fn normal_at(&self, pos: Vec3, normal: Vec3, base: Vec3, strength: f32) -> Vec3 {
+        let mut slope: Vec3 = 0.into();
+        self.map_point(pos, normal, |point| {
+            let value = self.sample_uv(point.u, point.v) * 2.0 - 1.0;
+            // Keep the directions flat against the surface.
+            let right = point.right - base * point.right.dot(base);
+            let up = point.up - base * point.up.dot(base);
+            slope += (right * value.x + up * value.y) / value.z.max(0.1) * point.weight;
+        });
+        (base + slope * strength).normalized()
+    }
+}
+
+/// Where a point on the surface lands on an image.
+struct MappedPoint {
+    /// How much this point contributes when there is more than one of them.
+    weight: f32,
+    u: f32,
+    v: f32,
+    /// Direction along the surface which goes right across the image.
+    right: Vec3,
+    /// Direction along the surface which goes up the image.
+    up: Vec3,
+}
+
+impl Texture for ImageTexture {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        let mut total: Vec3 = 0.into();
+        self.map_point(pos, normal, |point| {
+            total += self.sample_uv(point.u, point.v) * point.weight;
+        });
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_empty_image_is_an_error() {
+        assert!(ImageTexture::from_image(&RgbImage::new(0, 4), true).is_err());
+        assert!(ImageTexture::from_image(&RgbImage::new(1, 1), true).is_ok());
+    }
+}
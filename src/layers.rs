@@ -0,0 +1,119 @@
+use crate::{Material, MaterialSample, Vec3};
+
+/// A clear glossy layer on top of a material, like the lacquer on car paint or varnished wood.
+#[derive(Clone, Copy, Debug)]
+pub struct Clearcoat {
+    /// 0 is no coat at all, 1 is a full coat.
+    pub amount: f32,
+    /// Roughness of the coat itself, separate from the roughness of the material underneath.
+    pub roughness: f32,
+    /// Index of refraction of the coat, which controls how strongly it reflects. 1.5 suits most
+    /// lacquers and varnishes.
+    pub ior: f32,
+}
+
+impl Default for Clearcoat {
+    fn default() -> Self {
+        Self {
+            amount: 1.0,
+            roughness: 0.05,
+            ior: 1.5,
+        }
+    }
+}
+
+impl Clearcoat {
+    pub(crate) fn mix(self, other: Clearcoat, factor: f32) -> Clearcoat {
+        let mix = |a: f32, b: f32| a + (b - a) * factor;
+        Clearcoat {
+            amount: mix(self.amount, other.amount),
+            roughness: mix(self.roughness, other.roughness),
+            ior: mix(self.ior, other.ior),
+        }
+    }
+}
+
+/// A soft glow around the edges of a surface from light catching tiny fibers sticking out of
+/// it, like on velvet and other cloth.
+#[derive(Clone, Copy, Debug)]
+pub struct Sheen {
+    pub color: Vec3,
+    /// Low roughness keeps the glow to the very edges, high roughness spreads it further in.
+    pub roughness: f32,
+}
+
+impl Default for Sheen {
+    fn default() -> Self {
+        Self {
+            color: 1.into(),
+            roughness: 0.5,
+        }
+    }
+}
+
+impl Sheen {
+    pub fn with_color<T: Into<Vec3>>(mut self, color: T) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub(crate) fn mix(self, other: Sheen, factor: f32) -> Sheen {
+        Sheen {
+            color: self.color + (other.color - self.color) * factor,
+            roughness: self.roughness + (other.roughness - self.roughness) * factor,
+        }
+    }
+}
+
+/// A transparent film a few hundred nanometers thick on the surface, like a soap bubble, oil on
+/// water or heat tinted metal. Light reflecting off the top and bottom of the film interferes,
+/// which makes rainbow colors that shift with the angle the surface is seen from.
+#[derive(Clone, Copy, Debug)]
+pub struct ThinFilm {
+    /// Thickness of the film in nanometers. Colors show up best between about 200 and 1000.
+    pub thickness: f32,
+    /// Index of refraction of the film, which should be lower than the surface underneath.
+    pub ior: f32,
+}
+
+impl Default for ThinFilm {
+    fn default() -> Self {
+        Self {
+            thickness: 400.0,
+            ior: 1.33,
+        }
+    }
+}
+
+impl ThinFilm {
+    pub(crate) fn mix(self, other: ThinFilm, factor: f32) -> ThinFilm {
+        let mix = |a: f32, b: f32| a + (b - a) * factor;
+        ThinFilm {
+            thickness: mix(self.thickness, other.thickness),
+            ior: mix(self.ior, other.ior),
+        }
+    }
+}
+
+/// Adds layers on top of another material. From the top down, the clear coat goes over the
+/// sheen, which goes over the material with the thin film on its surface. Each layer only lets
+/// through the light it doesn't reflect itself, so adding layers never makes the material
+/// reflect more light than it receives. Layers which are None are left as the material
+/// underneath has them.
+#[derive(Clone)]
+pub struct LayeredMaterial<M: Material> {
+    pub base: M,
+    pub clearcoat: Option<Clearcoat>,
+    pub sheen: Option<Sheen>,
+    pub thin_film: Option<ThinFilm>,
+}
+
+impl<M: Material> Material for LayeredMaterial<M> {
+    fn sample(&self, pos: Vec3, normal: Vec3) -> MaterialSample {
+        let mut sample = self.base.sample(pos, normal);
+        sample.clearcoat = self.clearcoat.or(sample.clearcoat);
+        sample.sheen = self.sheen.or(sample.sheen);
+        sample.thin_film = self.thin_film.or(sample.thin_film);
+        sample
+    }
+}
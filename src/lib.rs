@@ -1,17 +1,57 @@
+mod bounds;
+mod bump;
+mod bvh;
+mod camera;
+mod csg;
+mod deform;
+mod domain;
+mod image_texture;
+mod layers;
 mod lights;
 mod material;
+mod matrix;
+mod media;
+mod modifiers;
+mod noise;
+mod normals;
 mod objects;
 mod post_process;
+mod projection;
+mod quaternion;
 mod renderer;
 mod scene;
+mod shading;
+mod spectrum;
+mod texture;
+mod tracer;
 mod util;
 mod vec;
 
+pub use bounds::*;
+pub use bump::*;
+pub(crate) use bvh::*;
+pub use camera::*;
+pub use csg::*;
+pub use deform::*;
+pub use domain::*;
+pub use image_texture::*;
+pub use layers::*;
 pub use lights::*;
 pub use material::*;
+pub use matrix::*;
+pub use media::*;
+pub use modifiers::*;
+pub use noise::*;
+pub use normals::*;
 pub use objects::*;
 pub use post_process::*;
+pub use projection::*;
+pub use quaternion::*;
 pub use renderer::*;
 pub use scene::*;
+pub(crate) use shading::*;
+pub use spectrum::*;
+pub use texture::*;
+pub use tracer::*;
 pub(crate) use util::*;
 pub use vec::*;
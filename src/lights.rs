@@ -2,7 +2,7 @@ use crate::{Vec3, MAX_SDF_DISTANCE};
 use rand::Rng;
 use std::f32::consts::PI;
 
-pub trait ImmediateLight {
+pub trait ImmediateLight: Send + Sync {
     fn sample(&self, from: Vec3) -> LightSample;
 }
 
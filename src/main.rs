@@ -1,4 +1,5 @@
 use raymarch_scratchpad::*;
+use std::sync::Arc;
 
 fn main() {
     let mut scene = Scene::new();
@@ -26,11 +27,17 @@ fn main() {
     });
 
     let renderer = Renderer {
-        size: 100,
+        camera: Camera {
+            projection: Arc::new(Perspective { fov: 33.4 }),
+            ..Default::default()
+        },
         samples: 256,
         num_bounces: 20,
-        camera_size: 0.3,
         pixel_size: 0.667,
+        threads: 0,
+        tile_size: 16,
+        tracer: Tracer::default(),
+        spectral: false,
         post_process: (AdjustExposure(1.5), AcesFilmicCurve),
     };
     renderer.render(&scene, "test.png");
@@ -1,34 +1,273 @@
-use crate::Vec3;
+use crate::{
+    Bumped, Channel, Clearcoat, Dispersion, ImageTexture, NormalMapped, Sheen, Texture, Textured,
+    ThinFilm, Vec3, REFERENCE_WAVELENGTH,
+};
+
+/// The different ways light can bounce off a surface. Each bounce follows just one of them,
+/// picked at random by MaterialSample::pick_lobe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lobe {
+    /// Light entering a non-metal and scattering straight back out in every direction.
+    Diffuse,
+    /// Light reflecting off the surface of the material.
+    Specular,
+    /// Light reflecting off the clear coat.
+    Clearcoat,
+    /// Light catching the fibers of the sheen.
+    Sheen,
+    /// Light entering a translucent object and coming back out somewhere else. This doesn't
+    /// bounce in any one direction, so the scene follows it separately.
+    Subsurface,
+}
 
 pub struct MaterialSample {
     pub base_color: Vec3,
     pub emission: Vec3,
+    /// 0 is perfectly smooth like a mirror, 1 is completely rough.
+    pub roughness: f32,
+    /// 0 is a plastic, paint or other non-metal where light bounces around under the surface
+    /// before coming back out, 1 is bare metal which only reflects light off its surface.
+    pub metallic: f32,
+    /// How shiny non-metals are, where 0.5 is about right for most materials and 0 turns off
+    /// reflections entirely. Metals get their reflections from base_color instead.
+    pub specular: f32,
+    /// 0 is opaque, 1 is completely see-through like glass or water. Light passing through the
+    /// surface gets bent according to ior. Transparent surfaces are always perfectly smooth.
+    /// Light inside a transparent object only looks for the way back out of that object, so
+    /// other objects inside it don't show up.
+    pub transmission: f32,
+    /// Index of refraction, about 1.33 for water, 1.5 for glass and 2.4 for diamond.
+    pub ior: f32,
+    /// How much ior changes with the wavelength of light.
+    pub dispersion: Dispersion,
+    /// How much of each color gets absorbed for every unit of distance light travels inside a
+    /// transparent object. See absorption_from_color for an easier way to pick this.
+    pub absorption: Vec3,
+    /// 0 is opaque, 1 makes light which would scatter back out of a non-metal right where it
+    /// entered wander around inside the object first, so it comes out somewhere else and glows
+    /// through thin parts, like skin, wax or marble. Light which doesn't make it back out
+    /// gets absorbed.
+    pub subsurface: f32,
+    /// Chance of each color surviving every time light scatters under the surface. Even a
+    /// little below 1 makes a big difference, since light scatters many times before coming
+    /// back out.
+    pub subsurface_albedo: Vec3,
+    /// Average distance each color travels under the surface before scattering.
+    pub mean_free_path: Vec3,
+    /// Layers on top of the material. See LayeredMaterial.
+    pub clearcoat: Option<Clearcoat>,
+    pub sheen: Option<Sheen>,
+    pub thin_film: Option<ThinFilm>,
+    /// Replaces the surface normal when working out how light bounces off the surface, which
+    /// adds detail like scratches or bumps without changing the shape of the object. None uses
+    /// the surface normal. Transparent surfaces ignore this.
+    pub normal: Option<Vec3>,
+}
+
+/// Returns absorption which makes white light turn the given color after travelling the given
+/// distance through a transparent object.
+pub fn absorption_from_color<T: Into<Vec3>>(color: T, distance: f32) -> Vec3 {
+    let color = color.into();
+    let absorption = |c: f32| -c.max(1e-6).ln() / distance;
+    Vec3::new(
+        absorption(color.x),
+        absorption(color.y),
+        absorption(color.z),
+    )
+}
+
+impl Default for MaterialSample {
+    fn default() -> Self {
+        Self {
+            base_color: 0.9.into(),
+            emission: 0.into(),
+            roughness: 0.5,
+            metallic: 0.0,
+            specular: 0.0,
+            transmission: 0.0,
+            ior: 1.5,
+            dispersion: Dispersion::None,
+            absorption: 0.into(),
+            subsurface: 0.0,
+            subsurface_albedo: 0.95.into(),
+            mean_free_path: 0.05.into(),
+            clearcoat: None,
+            sheen: None,
+            thin_film: None,
+            normal: None,
+        }
+    }
+}
+
+impl MaterialSample {
+    /// Blends between this sample and another one. A factor of 0 returns this sample, 1 returns
+    /// the other one.
+    pub fn mix(self, other: MaterialSample, factor: f32) -> MaterialSample {
+        let mix = |a: Vec3, b: Vec3| a + (b - a) * factor;
+        let mix_f32 = |a: f32, b: f32| a + (b - a) * factor;
+        MaterialSample {
+            base_color: mix(self.base_color, other.base_color),
+            emission: mix(self.emission, other.emission),
+            roughness: mix_f32(self.roughness, other.roughness),
+            metallic: mix_f32(self.metallic, other.metallic),
+            specular: mix_f32(self.specular, other.specular),
+            transmission: mix_f32(self.transmission, other.transmission),
+            ior: mix_f32(self.ior, other.ior),
+            // There's no way to blend between two different kinds of curve.
+            dispersion: if factor < 0.5 {
+                self.dispersion
+            } else {
+                other.dispersion
+            },
+            absorption: mix(self.absorption, other.absorption),
+            subsurface: mix_f32(self.subsurface, other.subsurface),
+            subsurface_albedo: mix(self.subsurface_albedo, other.subsurface_albedo),
+            mean_free_path: mix(self.mean_free_path, other.mean_free_path),
+            // A missing layer blends like one which is there but has no effect.
+            clearcoat: match (self.clearcoat, other.clearcoat) {
+                (Some(a), Some(b)) => Some(a.mix(b, factor)),
+                (Some(a), None) => Some(a.mix(Clearcoat { amount: 0.0, ..a }, factor)),
+                (None, Some(b)) => Some(Clearcoat { amount: 0.0, ..b }.mix(b, factor)),
+                (None, None) => None,
+            },
+            sheen: match (self.sheen, other.sheen) {
+                (Some(a), Some(b)) => Some(a.mix(b, factor)),
+                (Some(a), None) => Some(a.mix(a.with_color(0), factor)),
+                (None, Some(b)) => Some(b.with_color(0).mix(b, factor)),
+                (None, None) => None,
+            },
+            // Any film at all changes the colors, so there's nothing to blend a single one with.
+            thin_film: match (self.thin_film, other.thin_film) {
+                (Some(a), Some(b)) => Some(a.mix(b, factor)),
+                _ if factor < 0.5 => self.thin_film,
+                _ => other.thin_film,
+            },
+            // Without knowing the surface normal there's nothing to blend a single changed
+            // normal with.
+            normal: match (self.normal, other.normal) {
+                (Some(a), Some(b)) => Some(mix(a, b).normalized()),
+                _ if factor < 0.5 => self.normal,
+                _ => other.normal,
+            },
+        }
+    }
+
+    /// Changes the shading normal, if there is one, and normalizes the result. Objects which
+    /// move points around before looking up the material of another object use this to turn
+    /// the normal back the other way.
+    pub fn map_normal<F: FnOnce(Vec3) -> Vec3>(mut self, f: F) -> Self {
+        self.normal = self.normal.map(|normal| f(normal).normalized());
+        self
+    }
+
+    /// Returns the index of refraction for the given wavelength in nanometers, or for yellow
+    /// light if no wavelength is given.
+    pub fn ior_at(&self, wavelength: Option<f32>) -> f32 {
+        self.dispersion
+            .ior_at(self.ior, wavelength.unwrap_or(REFERENCE_WAVELENGTH))
+    }
 }
 
-pub trait Material {
-    fn sample(&self, pos: Vec3) -> MaterialSample;
+pub trait Material: Send + Sync {
+    /// Returns the material at a point on the surface, where normal is the surface normal there.
+    fn sample(&self, pos: Vec3, normal: Vec3) -> MaterialSample;
+
+    /// Replaces one channel of the material with the value of a texture.
+    fn textured<T: Texture>(self, channel: Channel, texture: T) -> Textured<Self, T>
+    where
+        Self: Sized,
+    {
+        Textured {
+            material: self,
+            channel,
+            texture,
+        }
+    }
+
+    /// Makes the surface look bumpy without changing its shape. Strength is how far outwards a
+    /// height of 1 would push the surface.
+    fn bumped<T: Texture>(self, height: T, strength: f32) -> Bumped<Self, T>
+    where
+        Self: Sized,
+    {
+        Bumped {
+            material: self,
+            height,
+            strength,
+        }
+    }
+
+    /// Makes the surface look bumpy using a normal map, which should be loaded with
+    /// ImageTexture::open_linear. A strength of 1 uses the normal map as it is, higher values
+    /// make the bumps steeper.
+    fn normal_mapped(self, map: ImageTexture, strength: f32) -> NormalMapped<Self>
+    where
+        Self: Sized,
+    {
+        NormalMapped {
+            material: self,
+            map,
+            strength,
+        }
+    }
 }
 
+/// The same everywhere. See MaterialSample for what each field does.
 #[derive(Clone)]
 pub struct BasicMaterial {
     pub base_color: Vec3,
     pub emission: Vec3,
+    pub roughness: f32,
+    pub metallic: f32,
+    pub specular: f32,
+    pub transmission: f32,
+    pub ior: f32,
+    pub dispersion: Dispersion,
+    pub absorption: Vec3,
+    pub subsurface: f32,
+    pub subsurface_albedo: Vec3,
+    pub mean_free_path: Vec3,
 }
 
 impl Default for BasicMaterial {
     fn default() -> Self {
+        let sample = MaterialSample::default();
         Self {
-            base_color: 0.9.into(),
-            emission: 0.into(),
+            base_color: sample.base_color,
+            emission: sample.emission,
+            roughness: sample.roughness,
+            metallic: sample.metallic,
+            specular: sample.specular,
+            transmission: sample.transmission,
+            ior: sample.ior,
+            dispersion: sample.dispersion,
+            absorption: sample.absorption,
+            subsurface: sample.subsurface,
+            subsurface_albedo: sample.subsurface_albedo,
+            mean_free_path: sample.mean_free_path,
         }
     }
 }
 
 impl Material for BasicMaterial {
-    fn sample(&self, _pos: Vec3) -> MaterialSample {
+    fn sample(&self, _pos: Vec3, _normal: Vec3) -> MaterialSample {
         MaterialSample {
             base_color: self.base_color,
             emission: self.emission,
+            roughness: self.roughness,
+            metallic: self.metallic,
+            specular: self.specular,
+            transmission: self.transmission,
+            ior: self.ior,
+            dispersion: self.dispersion,
+            absorption: self.absorption,
+            subsurface: self.subsurface,
+            subsurface_albedo: self.subsurface_albedo,
+            mean_free_path: self.mean_free_path,
+            clearcoat: None,
+            sheen: None,
+            thin_film: None,
+            normal: None,
         }
     }
 }
@@ -0,0 +1,159 @@
+use crate::{Quaternion, Vec3};
+use std::ops::Mul;
+
+/// A 4x4 matrix stored row by row. Only affine transformations are supported, so the bottom row
+/// is always treated as (0, 0, 0, 1).
+#[derive(Clone, Copy, Debug)]
+pub struct Mat4 {
+    pub rows: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const fn identity() -> Self {
+        Self {
+            rows: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn translation<V: Into<Vec3>>(offset: V) -> Self {
+        let offset = offset.into();
+        let mut result = Self::identity();
+        result.rows[0][3] = offset.x;
+        result.rows[1][3] = offset.y;
+        result.rows[2][3] = offset.z;
+        result
+    }
+
+    pub fn scale<V: Into<Vec3>>(scale: V) -> Self {
+        let scale = scale.into();
+        let mut result = Self::identity();
+        result.rows[0][0] = scale.x;
+        result.rows[1][1] = scale.y;
+        result.rows[2][2] = scale.z;
+        result
+    }
+
+    pub fn rotation(rotation: Quaternion) -> Self {
+        let x = rotation.rotate((1, 0, 0));
+        let y = rotation.rotate((0, 1, 0));
+        let z = rotation.rotate((0, 0, 1));
+        Self {
+            rows: [
+                [x.x, y.x, z.x, 0.0],
+                [x.y, y.y, z.y, 0.0],
+                [x.z, y.z, z.z, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Transforms a position, so translation is applied.
+    pub fn transform_point<V: Into<Vec3>>(&self, point: V) -> Vec3 {
+        self.transform_vector(point) + (self.rows[0][3], self.rows[1][3], self.rows[2][3])
+    }
+
+    /// Transforms a direction or offset, so translation is not applied.
+    pub fn transform_vector<V: Into<Vec3>>(&self, vector: V) -> Vec3 {
+        let v = vector.into();
+        let row = |i: usize| Vec3::new(self.rows[i][0], self.rows[i][1], self.rows[i][2]);
+        Vec3::new(row(0).dot(v), row(1).dot(v), row(2).dot(v))
+    }
+
+    /// Transforms a direction by the transpose of this matrix. Normals need to be transformed
+    /// this way to stay perpendicular to surfaces.
+    pub fn transpose_transform_vector<V: Into<Vec3>>(&self, vector: V) -> Vec3 {
+        let v = vector.into();
+        let column = |i: usize| Vec3::new(self.rows[0][i], self.rows[1][i], self.rows[2][i]);
+        Vec3::new(column(0).dot(v), column(1).dot(v), column(2).dot(v))
+    }
+
+    /// Returns the matrix that undoes this transformation, or None if the matrix squishes space
+    /// flat and so cannot be undone.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.rows;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        // Inverse of the top left 3x3 section, computed from its adjugate.
+        let adjugate = [
+            [
+                cofactor(1, 2, 1, 2),
+                -cofactor(0, 2, 1, 2),
+                cofactor(0, 1, 1, 2),
+            ],
+            [
+                -cofactor(1, 2, 0, 2),
+                cofactor(0, 2, 0, 2),
+                -cofactor(0, 1, 0, 2),
+            ],
+            [
+                cofactor(1, 2, 0, 1),
+                -cofactor(0, 2, 0, 1),
+                cofactor(0, 1, 0, 1),
+            ],
+        ];
+        let determinant =
+            m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+        // Tiny scales give tiny determinants which are still fine to divide by, so only reject
+        // ones which are exactly zero or have already gone wrong.
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let mut result = Self::identity();
+        for (r, adjugate_row) in adjugate.iter().enumerate() {
+            for (c, value) in adjugate_row.iter().enumerate() {
+                result.rows[r][c] = value / determinant;
+            }
+        }
+        let translation = result.transform_vector((m[0][3], m[1][3], m[2][3]));
+        result.rows[0][3] = -translation.x;
+        result.rows[1][3] = -translation.y;
+        result.rows[2][3] = -translation.z;
+        let finite = result.rows.iter().flatten().all(|value| value.is_finite());
+        finite.then_some(result)
+    }
+
+    /// Returns the most that this transformation can stretch the length of any vector.
+    pub fn max_stretch(&self) -> f32 {
+        // Power iteration on (M^T)M converges to the direction that gets stretched the most.
+        let mut v = Vec3::new(1.0, 0.7, 0.3).normalized();
+        let mut stretch = 0.0;
+        for _ in 0..64 {
+            let transformed = self.transform_vector(v);
+            stretch = transformed.magnitude();
+            let back = self.transpose_transform_vector(transformed);
+            if back.magnitude() == 0.0 {
+                break;
+            }
+            v = back.normalized();
+        }
+        stretch
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Multiplying two matrices produces a transformation that does the right hand side
+/// transformation first and then the left hand side transformation.
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut result = Self::identity();
+        for r in 0..3 {
+            for c in 0..4 {
+                result.rows[r][c] = (0..4).map(|i| self.rows[r][i] * other.rows[i][c]).sum();
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{RenderedObject, Texture, Vec3};
+use rand::Rng;
+use std::f32::consts::PI;
+
+/// Something light can scatter off of or get absorbed by while travelling through it, like fog,
+/// smoke or clouds.
+pub trait Medium: Send + Sync {
+    /// Returns how much of the light gets scattered and how much gets absorbed per unit of
+    /// distance travelled at a point.
+    fn coefficients_at(&self, point: Vec3) -> (Vec3, Vec3);
+
+    /// Controls which way light tends to scatter, from -1 for straight back the way it came
+    /// through 0 for every direction equally to 1 for onwards in the same direction.
+    fn anisotropy(&self) -> f32;
+
+    /// Returns the most that any component of scattering plus absorption can be on the part of
+    /// the ray between start and end. Overestimating makes rendering slower, underestimating
+    /// makes it wrong.
+    fn max_extinction(&self, origin: Vec3, direction: Vec3, start: f32, end: f32) -> f32;
+}
+
+/// Fog which fills the whole scene and thins out exponentially going up.
+#[derive(Clone, Debug)]
+pub struct HeightFog {
+    pub scattering: Vec3,
+    pub absorption: Vec3,
+    pub anisotropy: f32,
+    /// The fog has its full density below this height.
+    pub height: f32,
+    /// How quickly the fog thins out above height. The density halves every 0.7 / falloff
+    /// units.
+    pub falloff: f32,
+}
+
+impl HeightFog {
+    fn density_at(&self, y: f32) -> f32 {
+        // Y points down, so the fog thins out as y gets smaller.
+        (-self.falloff * (self.height - y).max(0.0)).exp()
+    }
+}
+
+impl Medium for HeightFog {
+    fn coefficients_at(&self, point: Vec3) -> (Vec3, Vec3) {
+        let density = self.density_at(point.y);
+        (self.scattering * density, self.absorption * density)
+    }
+
+    fn anisotropy(&self) -> f32 {
+        self.anisotropy
+    }
+
+    fn max_extinction(&self, origin: Vec3, direction: Vec3, start: f32, end: f32) -> f32 {
+        // The height changes steadily along the ray, so the densest point is at one of the ends.
+        let density = self
+            .density_at(origin.y + direction.y * start)
+            .max(self.density_at(origin.y + direction.y * end));
+        (self.scattering + self.absorption).max_component() * density
+    }
+}
+
+/// A medium which fills the inside of an object, with a density between 0 and 1 given by a
+/// solid texture like Fbm noise. Values outside that range are clamped.
+pub struct Volume<T: RenderedObject, D: Texture> {
+    pub shape: T,
+    pub density: D,
+    /// Scattering and absorption where the density is 1.
+    pub scattering: Vec3,
+    pub absorption: Vec3,
+    pub anisotropy: f32,
+}
+
+impl<T: RenderedObject, D: Texture> Medium for Volume<T, D> {
+    fn coefficients_at(&self, point: Vec3) -> (Vec3, Vec3) {
+        if self.shape.distance_to(point) > 0.0 {
+            return (0.into(), 0.into());
+        }
+        // There's no surface to take a normal from.
+        let density = self.density.value_at(point, 0.into()).average();
+        let density = density.clamp(0.0, 1.0);
+        (self.scattering * density, self.absorption * density)
+    }
+
+    fn anisotropy(&self) -> f32 {
+        self.anisotropy
+    }
+
+    fn max_extinction(&self, origin: Vec3, direction: Vec3, start: f32, end: f32) -> f32 {
+        match self.shape.bounds().intersect_ray(origin, direction) {
+            Some((enter, exit)) if enter < end && exit > start => {
+                (self.scattering + self.absorption).max_component()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Henyey-Greenstein phase function, giving how much of the light scatters by an angle with the
+/// given cosine.
+pub(crate) fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+/// Picks a direction for light travelling in the given direction to scatter towards, following
+/// the Henyey-Greenstein phase function.
+pub(crate) fn sample_henyey_greenstein(direction: Vec3, g: f32) -> Vec3 {
+    let mut rng = rand::thread_rng();
+    let (u, v): (f32, f32) = (rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+    let cos_theta = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u
+    } else {
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let (tangent, bitangent) = direction.make_two_perpendicular();
+    let around = 2.0 * PI * v;
+    tangent.normalized() * sin_theta * around.cos()
+        + bitangent.normalized() * sin_theta * around.sin()
+        + direction * cos_theta
+}
+
+/// What happened to a ray travelling through the media in a scene.
+pub(crate) enum MediumEvent {
+    /// The ray made it through, and what it finds at the end should be multiplied by the weight.
+    Passed(Vec3),
+    Absorbed,
+    /// The ray scattered at the given distance. The light scattering towards where the ray came
+    /// from should be multiplied by the weight.
+    Scattered {
+        distance: f32,
+        weight: Vec3,
+        anisotropy: f32,
+    },
+}
+
+/// Randomly picks points along a ray where the ray might collide with the media, more often
+/// where the media could be denser. Gives the distance of each point along with the combined
+/// max_extinction of the media there.
+struct TentativeCollisions<'a> {
+    media: &'a [Box<dyn Medium>],
+    origin: Vec3,
+    direction: Vec3,
+    length: f32,
+    /// The ray is split into pieces which double in length, so that each one gets a tighter
+    /// bound on the extinction than the whole ray would.
+    piece_end: f32,
+    majorant: f32,
+    travelled: f32,
+}
+
+impl<'a> TentativeCollisions<'a> {
+    fn new(media: &'a [Box<dyn Medium>], origin: Vec3, direction: Vec3, length: f32) -> Self {
+        Self {
+            media,
+            origin,
+            direction,
+            length,
+            piece_end: 0.0,
+            majorant: 0.0,
+            travelled: 0.0,
+        }
+    }
+}
+
+impl Iterator for TentativeCollisions<'_> {
+    type Item = (f32, f32);
+
+    fn next(&mut self) -> Option<(f32, f32)> {
+        let mut rng = rand::thread_rng();
+        loop {
+            if self.majorant > 0.0 {
+                let step = -(1.0 - rng.gen_range(0.0f32, 1.0)).ln() / self.majorant;
+                if self.travelled + step < self.piece_end {
+                    self.travelled += step;
+                    return Some((self.travelled, self.majorant));
+                }
+            }
+            // Nothing happens on the rest of this piece, which doesn't change what happens on
+            // the next one.
+            self.travelled = self.piece_end;
+            if self.travelled >= self.length {
+                return None;
+            }
+            self.piece_end = (self.travelled * 2.0).max(1.0).min(self.length);
+            let (origin, direction, start, end) =
+                (self.origin, self.direction, self.travelled, self.piece_end);
+            self.majorant = self
+                .media
+                .iter()
+                .map(|medium| medium.max_extinction(origin, direction, start, end))
+                .sum();
+        }
+    }
+}
+
+/// Follows a ray through the media for up to the given distance to find where it first scatters
+/// or gets absorbed. Uses delta tracking, which handles media whose density changes by
+/// pretending every medium has the same constant density and letting some of the collisions
+/// pass through harmlessly. Colored media use spectral tracking, where the chance of each
+/// outcome is averaged over the channels and the weight makes up the difference.
+pub(crate) fn track_through_media(
+    media: &[Box<dyn Medium>],
+    origin: Vec3,
+    direction: Vec3,
+    length: f32,
+) -> MediumEvent {
+    let mut rng = rand::thread_rng();
+    let mut weight: Vec3 = 1.into();
+    for (distance, majorant) in TentativeCollisions::new(media, origin, direction, length) {
+        let point = origin + direction * distance;
+        let (mut scattering, mut absorption): (Vec3, Vec3) = (0.into(), 0.into());
+        for medium in media {
+            let (s, a) = medium.coefficients_at(point);
+            scattering += s;
+            absorption += a;
+        }
+        let null = (Vec3::from(majorant) - scattering - absorption).max(0);
+        let chance_absorbed = (absorption * weight).average();
+        let chance_scattered = (scattering * weight).average();
+        let chance_null = (null * weight).average();
+        let total = chance_absorbed + chance_scattered + chance_null;
+        if total <= 0.0 {
+            return MediumEvent::Absorbed;
+        }
+        let choice = rng.gen_range(0.0, total);
+        if choice < chance_absorbed {
+            return MediumEvent::Absorbed;
+        } else if choice < chance_absorbed + chance_scattered {
+            weight *= scattering * total / (majorant * chance_scattered);
+            // Pick which medium did the scattering to decide which way the light goes.
+            let mut choice = rng.gen_range(0.0, scattering.average());
+            let mut anisotropy = 0.0;
+            for medium in media {
+                let scattered = medium.coefficients_at(point).0.average();
+                if scattered > 0.0 {
+                    anisotropy = medium.anisotropy();
+                    if choice < scattered {
+                        break;
+                    }
+                    choice -= scattered;
+                }
+            }
+            return MediumEvent::Scattered {
+                distance,
+                weight,
+                anisotropy,
+            };
+        }
+        weight *= null * total / (majorant * chance_null);
+    }
+    MediumEvent::Passed(weight)
+}
+
+/// Returns how much of the light travelling along a ray makes it the given distance through
+/// the media without being scattered or absorbed, using ratio tracking.
+pub(crate) fn transmittance_through_media(
+    media: &[Box<dyn Medium>],
+    origin: Vec3,
+    direction: Vec3,
+    length: f32,
+) -> Vec3 {
+    let mut rng = rand::thread_rng();
+    let mut transmittance: Vec3 = 1.into();
+    for (distance, majorant) in TentativeCollisions::new(media, origin, direction, length) {
+        let point = origin + direction * distance;
+        let mut extinction: Vec3 = 0.into();
+        for medium in media {
+            let (s, a) = medium.coefficients_at(point);
+            extinction += s + a;
+        }
+        transmittance *= (Vec3::from(1) - extinction / majorant).max(0);
+        // Randomly give up on rays which are barely letting any light through, and make the
+        // ones which keep going brighter to make up for it.
+        if transmittance.max_component() < 0.1 {
+            if rng.gen_range(0.0, 1.0) < 0.5 {
+                return 0.into();
+            }
+            transmittance *= 2.0;
+        }
+    }
+    transmittance
+}
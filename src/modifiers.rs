@@ -0,0 +1,54 @@
+use crate::{BoundingBox, MaterialSample, RenderedObject, Vec3};
+
+/// Moves the surface of the object outwards by a constant amount, or inwards if the amount is
+/// negative. Growing an object this way also rounds off its edges and corners.
+pub struct Offset<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) amount: f32,
+}
+
+impl<T: RenderedObject> RenderedObject for Offset<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(point) - self.amount
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds().expanded(self.amount.max(0.0))
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        self.object.gradient(point)
+    }
+}
+
+/// Hollows out the object, leaving a wall centered on the original surface.
+pub struct Shell<T: RenderedObject> {
+    pub(crate) object: T,
+    pub(crate) thickness: f32,
+    /// Each layer after the first splits every wall into two walls half as thick.
+    pub(crate) layers: u32,
+}
+
+impl<T: RenderedObject> RenderedObject for Shell<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let mut distance = self.object.distance_to(point);
+        let mut half_thickness = self.thickness / 2.0;
+        for _ in 0..self.layers.max(1) {
+            distance = distance.abs() - half_thickness;
+            half_thickness /= 2.0;
+        }
+        distance
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds().expanded(self.thickness / 2.0)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{Texture, Vec3};
+
+/// Scrambles a lattice point and a seed into a random looking number.
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed.wrapping_mul(0x9E37_79B9);
+    for v in [x, y, z] {
+        h = (h ^ v as u32).wrapping_mul(0x27D4_EB2D);
+        h ^= h >> 15;
+    }
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^ (h >> 12)
+}
+
+/// Turns a hash into a number between 0 and 1.
+fn hash_to_unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1 << 24) as f32
+}
+
+/// One of the twelve directions from the center of a cube to the middle of its edges.
+fn gradient_direction(h: u32) -> Vec3 {
+    const DIRECTIONS: [(f32, f32, f32); 12] = [
+        (1.0, 1.0, 0.0),
+        (-1.0, 1.0, 0.0),
+        (1.0, -1.0, 0.0),
+        (-1.0, -1.0, 0.0),
+        (1.0, 0.0, 1.0),
+        (-1.0, 0.0, 1.0),
+        (1.0, 0.0, -1.0),
+        (-1.0, 0.0, -1.0),
+        (0.0, 1.0, 1.0),
+        (0.0, -1.0, 1.0),
+        (0.0, 1.0, -1.0),
+        (0.0, -1.0, -1.0),
+    ];
+    DIRECTIONS[(h % 12) as usize].into()
+}
+
+fn floor(v: Vec3) -> (i32, i32, i32) {
+    (v.x.floor() as i32, v.y.floor() as i32, v.z.floor() as i32)
+}
+
+/// Smooth random bumps about scale units across, with values between 0 and 1.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    pub scale: f32,
+    pub seed: u32,
+}
+
+impl Texture for Perlin {
+    fn value_at(&self, pos: Vec3, _normal: Vec3) -> Vec3 {
+        let pos = pos / self.scale;
+        let (x, y, z) = floor(pos);
+        let local = pos - (x, y, z);
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let (u, v, w) = (fade(local.x), fade(local.y), fade(local.z));
+        let corner = |dx: i32, dy: i32, dz: i32| {
+            let h = hash(x + dx, y + dy, z + dz, self.seed);
+            gradient_direction(h).dot(local - (dx, dy, dz))
+        };
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let value = lerp(
+            lerp(
+                lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+                lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+                v,
+            ),
+            lerp(
+                lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+                lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+                v,
+            ),
+            w,
+        );
+        (value * 0.5 + 0.5).clamp(0.0, 1.0).into()
+    }
+}
+
+/// Like Perlin noise but cheaper and without the faint grid pattern, with values between 0
+/// and 1.
+#[derive(Clone, Debug)]
+pub struct Simplex {
+    pub scale: f32,
+    pub seed: u32,
+}
+
+impl Texture for Simplex {
+    fn value_at(&self, pos: Vec3, _normal: Vec3) -> Vec3 {
+        const SKEW: f32 = 1.0 / 3.0;
+        const UNSKEW: f32 = 1.0 / 6.0;
+        let pos = pos / self.scale;
+        // Find which tetrahedron of the skewed grid the point is in.
+        let (x, y, z) = floor(pos + pos.sum() * SKEW);
+        let origin = Vec3::from((x, y, z)) - (x + y + z) as f32 * UNSKEW;
+        let local = pos - origin;
+        let (second, third) = if local.x >= local.y {
+            if local.y >= local.z {
+                ((1, 0, 0), (1, 1, 0))
+            } else if local.x >= local.z {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if local.y < local.z {
+            ((0, 0, 1), (0, 1, 1))
+        } else if local.x < local.z {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+        let corners = [(0, 0, 0), second, third, (1, 1, 1)];
+        let mut value = 0.0;
+        for (index, &(dx, dy, dz)) in corners.iter().enumerate() {
+            let offset = local - (dx, dy, dz) + index as f32 * UNSKEW;
+            let falloff = 0.6 - offset.dot(offset);
+            if falloff > 0.0 {
+                let h = hash(x + dx, y + dy, z + dz, self.seed);
+                value += falloff.powi(4) * gradient_direction(h).dot(offset);
+            }
+        }
+        (value * 16.0 + 0.5).clamp(0.0, 1.0).into()
+    }
+}
+
+/// Fractal Brownian motion, which adds together smaller and smaller copies of another texture to
+/// get detail at every scale. Works best with noise textures.
+#[derive(Clone, Debug)]
+pub struct Fbm<T: Texture> {
+    pub texture: T,
+    /// How many copies to add together. With no copies at all the texture is 0 everywhere.
+    pub octaves: u32,
+    /// How much smaller each copy is than the last, usually 2.
+    pub lacunarity: f32,
+    /// How much weaker each copy is than the last, usually 0.5.
+    pub gain: f32,
+}
+
+impl<T: Texture> Texture for Fbm<T> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        if self.octaves == 0 {
+            return 0.into();
+        }
+        let mut total: Vec3 = 0.into();
+        let mut total_weight = 0.0;
+        let mut frequency = 1.0;
+        let mut weight = 1.0;
+        for octave in 0..self.octaves {
+            // Shift each copy so that they don't all line up at the origin.
+            let shift = Vec3::from(octave as f32 * 19.19);
+            total += self.texture.value_at(pos * frequency + shift, normal) * weight;
+            total_weight += weight;
+            frequency *= self.lacunarity;
+            weight *= self.gain;
+        }
+        total / total_weight
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoronoiOutput {
+    /// Distance to the closest random point, 0 at the point and around 1 far away from it.
+    Distance,
+    /// How far the closest point is from being the second closest point, which is 0 along the
+    /// borders between cells.
+    Edges,
+    /// A random value between 0 and 1 which is the same across each cell.
+    Cell,
+}
+
+/// Worley noise, which scatters random points around and splits space into cells around them.
+#[derive(Clone, Debug)]
+pub struct Voronoi {
+    /// Average distance between points.
+    pub scale: f32,
+    pub seed: u32,
+    pub output: VoronoiOutput,
+}
+
+impl Texture for Voronoi {
+    fn value_at(&self, pos: Vec3, _normal: Vec3) -> Vec3 {
+        let pos = pos / self.scale;
+        let (x, y, z) = floor(pos);
+        let (mut closest, mut second) = (f32::MAX, f32::MAX);
+        let mut closest_hash = 0;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let (cx, cy, cz) = (x + dx, y + dy, z + dz);
+                    let h = hash(cx, cy, cz, self.seed);
+                    let point = Vec3::from((cx, cy, cz))
+                        + (
+                            hash_to_unit(h),
+                            hash_to_unit(hash(cx, cy, cz, h)),
+                            hash_to_unit(hash(cx, cy, cz, h ^ 0x5555_5555)),
+                        );
+                    let distance = (point - pos).magnitude();
+                    if distance < closest {
+                        second = closest;
+                        closest = distance;
+                        closest_hash = h;
+                    } else if distance < second {
+                        second = distance;
+                    }
+                }
+            }
+        }
+        match self.output {
+            VoronoiOutput::Distance => closest.into(),
+            VoronoiOutput::Edges => (second - closest).into(),
+            VoronoiOutput::Cell => hash_to_unit(hash(0, 0, 0, closest_hash)).into(),
+        }
+    }
+}
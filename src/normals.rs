@@ -0,0 +1,55 @@
+use crate::{RenderedObject, Vec3};
+
+/// How surface normals are estimated for objects which can't work out their gradient exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NormalMethod {
+    /// Compares the distance at the point to the distance a little way along each axis. Takes
+    /// four evaluations and is slightly biased, since it only looks in one direction.
+    ForwardDifferences,
+    /// Compares the distances a little way in both directions along each axis. Takes six
+    /// evaluations.
+    CentralDifferences,
+    /// Compares the distances at the corners of a tetrahedron around the point. Takes four
+    /// evaluations and is about as accurate as central differences.
+    #[default]
+    Tetrahedral,
+}
+
+impl NormalMethod {
+    /// Returns the normalized surface normal of the object at the given point, using the
+    /// object's exact gradient if it has one.
+    pub fn normal_at(self, object: &dyn RenderedObject, point: Vec3) -> Vec3 {
+        const EPSILON: f32 = 1e-4;
+        if let Some(gradient) = object.gradient(point) {
+            return gradient.normalized();
+        }
+        let df = |offset: Vec3| object.distance_to(point + offset);
+        let gradient = match self {
+            Self::ForwardDifferences => {
+                let center = df(0.into());
+                Vec3::new(
+                    df((EPSILON, 0, 0).into()) - center,
+                    df((0, EPSILON, 0).into()) - center,
+                    df((0, 0, EPSILON).into()) - center,
+                )
+            }
+            Self::CentralDifferences => Vec3::new(
+                df((EPSILON, 0, 0).into()) - df((-EPSILON, 0, 0).into()),
+                df((0, EPSILON, 0).into()) - df((0, -EPSILON, 0).into()),
+                df((0, 0, EPSILON).into()) - df((0, 0, -EPSILON).into()),
+            ),
+            Self::Tetrahedral => {
+                let corners: [Vec3; 4] = [
+                    (1, -1, -1).into(),
+                    (-1, -1, 1).into(),
+                    (-1, 1, -1).into(),
+                    (1, 1, 1).into(),
+                ];
+                corners.iter().fold(Vec3::from(0), |sum, &corner| {
+                    sum + corner * df(corner * EPSILON)
+                })
+            }
+        };
+        gradient.normalized()
+    }
+}
@@ -1,8 +1,46 @@
-use crate::{Material, MaterialSample, Vec3};
+use crate::{
+    Bent, Blend, BoundingBox, Displaced, Elongated, Intersection, Mat4, Material, MaterialSample,
+    Mirrored, Offset, Quaternion, Repeated, RepeatedAround, SafetyFactor, Shell,
+    SmoothIntersection, SmoothSubtraction, SmoothUnion, Subtraction, Tapered, Twisted, Union, Vec3,
+    Xor,
+};
 
-pub trait RenderedObject {
+pub trait RenderedObject: Send + Sync {
     fn distance_to(&self, point: Vec3) -> f32;
-    fn material_at(&self, point: Vec3) -> MaterialSample;
+    /// Returns the material at a point on the surface, where normal is the surface normal there.
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample;
+
+    /// Returns a box the object fits entirely inside of. Smaller boxes let the scene skip
+    /// evaluating the object more often, but the box must never cut off any part of the object.
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::infinite()
+    }
+
+    /// Returns the direction the distance increases fastest in, for objects which can work it
+    /// out exactly. It does not need to be normalized. When this returns None, the scene
+    /// estimates the normal from nearby distances instead.
+    fn gradient(&self, _point: Vec3) -> Option<Vec3> {
+        None
+    }
+}
+
+/// Lets boxed objects, like ones picked at runtime, be used anywhere a plain object can.
+impl<T: RenderedObject + ?Sized> RenderedObject for Box<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        (**self).distance_to(point)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        (**self).material_at(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        (**self).bounds()
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        (**self).gradient(point)
+    }
 }
 
 pub trait Object: RenderedObject + Sized {
@@ -19,6 +57,248 @@ pub trait Object: RenderedObject + Sized {
             scale: num_traits::cast(scale).unwrap(),
         }
     }
+
+    /// Scales the object by a different amount along each axis. Returns None if any of the
+    /// scales is zero, since that squishes the object flat just like a singular transform.
+    fn scaled_by<V: Into<Vec3>>(self, scale: V) -> Option<ScaledBy<Self>> {
+        let scale = scale.into();
+        let finite = |v: Vec3| v.x.is_finite() && v.y.is_finite() && v.z.is_finite();
+        if !finite(scale) || !finite(Vec3::from(1) / scale) {
+            return None;
+        }
+        Some(ScaledBy {
+            object: self,
+            scale,
+            // Squishing an object makes distances shorter, so use the smallest scale to make
+            // sure we never overestimate the distance. Negative scales mirror the object, which
+            // doesn't change distances.
+            distance_factor: scale.x.abs().min(scale.y.abs()).min(scale.z.abs()),
+        })
+    }
+
+    fn rotated(self, rotation: Quaternion) -> Rotated<Self> {
+        Rotated {
+            object: self,
+            inverse_rotation: rotation.normalized().conjugate(),
+        }
+    }
+
+    /// Rotates the object counterclockwise around the given axis by the given number of
+    /// degrees.
+    fn rotated_around<V: Into<Vec3>>(self, axis: V, degrees: f32) -> Rotated<Self> {
+        self.rotated(Quaternion::from_axis_angle(axis, degrees))
+    }
+
+    /// Rotates the object around the X axis, then the Y axis, then the Z axis by the
+    /// corresponding number of degrees.
+    fn rotated_euler<V: Into<Vec3>>(self, degrees: V) -> Rotated<Self> {
+        self.rotated(Quaternion::from_euler(degrees))
+    }
+
+    /// Applies an affine transformation to the object. Returns None if the transformation
+    /// squishes space flat, since there would be no way to look up points in the object.
+    fn transformed(self, transform: Mat4) -> Option<Transformed<Self>> {
+        let inverse = transform.inverse()?;
+        Some(Transformed {
+            object: self,
+            transform,
+            inverse,
+            distance_factor: 1.0 / inverse.max_stretch(),
+        })
+    }
+
+    /// Repeats the object forever in a grid. Axes with a spacing of zero are not repeated
+    /// along.
+    fn repeated<V: Into<Vec3>>(self, spacing: V) -> Repeated<Self> {
+        Repeated {
+            object: self,
+            spacing: spacing.into(),
+            count: None,
+            cell_material: None,
+        }
+    }
+
+    /// Makes count copies of the object along each axis, starting at the original object and
+    /// going towards +X, +Y and +Z. Axes with a spacing of zero are not repeated along.
+    fn repeated_bounded<V: Into<Vec3>, C: Into<Vec3>>(
+        self,
+        spacing: V,
+        count: C,
+    ) -> Repeated<Self> {
+        Repeated {
+            object: self,
+            spacing: spacing.into(),
+            count: Some(count.into()),
+            cell_material: None,
+        }
+    }
+
+    /// Makes count copies of the object evenly spaced in a circle around the given axis. The
+    /// original copy should sit towards +X from the axis, or towards +Y if the axis is close to
+    /// the X axis.
+    fn repeated_around<V: Into<Vec3>>(self, axis: V, count: u32) -> RepeatedAround<Self> {
+        let axis = axis.into().normalized();
+        let reference: Vec3 = if axis.x.abs() < 0.9 {
+            (1, 0, 0).into()
+        } else {
+            (0, 1, 0).into()
+        };
+        let reference_x = (reference - axis * reference.dot(axis)).normalized();
+        RepeatedAround {
+            object: self,
+            axis,
+            reference_x,
+            reference_y: axis.cross(reference_x),
+            count,
+            cell_material: None,
+        }
+    }
+
+    /// Reflects the object across the plane through the origin with the given normal.
+    fn mirrored<V: Into<Vec3>>(self, normal: V) -> Mirrored<Self> {
+        Mirrored {
+            object: self,
+            normal: normal.into().normalized(),
+        }
+    }
+
+    /// Makes rays take smaller steps near this object, for when a deformation or displacement
+    /// makes its distance field overestimate how far away the surface is. factor should be
+    /// between 0 and 1, and smaller values are safer but slower.
+    fn with_safety_factor(self, factor: f32) -> SafetyFactor<Self> {
+        SafetyFactor {
+            object: self,
+            factor,
+        }
+    }
+
+    /// Twists the object around the given axis through the origin. Twisting stretches space, so
+    /// strong twists will need with_safety_factor.
+    fn twisted<V: Into<Vec3>>(self, axis: V, degrees_per_unit: f32) -> Twisted<Self> {
+        Twisted {
+            object: self,
+            axis: axis.into().normalized(),
+            degrees_per_unit,
+        }
+    }
+
+    /// Bends the object around the Z axis. Bending stretches space, so strong bends will need
+    /// with_safety_factor.
+    fn bent(self, degrees_per_unit: f32) -> Bent<Self> {
+        Bent {
+            object: self,
+            degrees_per_unit,
+        }
+    }
+
+    /// Makes the object get wider along the given axis through the origin, or thinner if rate
+    /// is negative.
+    fn tapered<V: Into<Vec3>>(self, axis: V, rate: f32) -> Tapered<Self> {
+        Tapered {
+            object: self,
+            axis: axis.into().normalized(),
+            rate,
+        }
+    }
+
+    /// Stretches the middle of the object by twice the given amount along each axis.
+    fn elongated<V: Into<Vec3>>(self, amount: V) -> Elongated<Self> {
+        Elongated {
+            object: self,
+            amount: amount.into(),
+        }
+    }
+
+    /// Moves the surface outwards by the result of the function. Unless the function changes
+    /// slowly, this will need with_safety_factor.
+    fn displaced<F: Fn(Vec3) -> f32 + Send + Sync>(self, displacement: F) -> Displaced<Self, F> {
+        Displaced {
+            object: self,
+            displacement,
+        }
+    }
+
+    /// Rounds off the edges and corners of the object with the given radius. This works by
+    /// growing the object, so it also gets bigger by radius in every direction. To keep the
+    /// original size, shrink the object by radius first, like
+    /// cube(mat, size - radius).rounded(radius).
+    fn rounded(self, radius: f32) -> Offset<Self> {
+        self.offset(radius)
+    }
+
+    /// Grows the object by the given amount in every direction, or shrinks it if the amount is
+    /// negative.
+    fn offset(self, amount: f32) -> Offset<Self> {
+        Offset {
+            object: self,
+            amount,
+        }
+    }
+
+    /// Hollows out the object, leaving a wall of the given thickness centered on its surface.
+    fn shell(self, thickness: f32) -> Shell<Self> {
+        self.shell_layers(thickness, 1)
+    }
+
+    /// Like shell, but each extra layer splits every wall into two concentric walls half as
+    /// thick as before.
+    fn shell_layers(self, thickness: f32, layers: u32) -> Shell<Self> {
+        Shell {
+            object: self,
+            thickness,
+            layers,
+        }
+    }
+
+    fn union<O: RenderedObject>(self, other: O) -> Union<Self, O> {
+        Union { a: self, b: other }
+    }
+
+    fn intersect<O: RenderedObject>(self, other: O) -> Intersection<Self, O> {
+        Intersection { a: self, b: other }
+    }
+
+    /// Carves the other object out of this one.
+    fn subtract<O: RenderedObject>(self, other: O) -> Subtraction<Self, O> {
+        Subtraction { a: self, b: other }
+    }
+
+    fn xor<O: RenderedObject>(self, other: O) -> Xor<Self, O> {
+        Xor { a: self, b: other }
+    }
+
+    /// Like union, but the two objects melt into each other where they are close together.
+    fn smooth_union<O: RenderedObject>(self, other: O, blend: Blend) -> SmoothUnion<Self, O> {
+        SmoothUnion {
+            a: self,
+            b: other,
+            blend,
+        }
+    }
+
+    fn smooth_intersect<O: RenderedObject>(
+        self,
+        other: O,
+        blend: Blend,
+    ) -> SmoothIntersection<Self, O> {
+        SmoothIntersection {
+            a: self,
+            b: other,
+            blend,
+        }
+    }
+
+    fn smooth_subtract<O: RenderedObject>(
+        self,
+        other: O,
+        blend: Blend,
+    ) -> SmoothSubtraction<Self, O> {
+        SmoothSubtraction {
+            a: self,
+            b: other,
+            blend,
+        }
+    }
 }
 
 impl<T: RenderedObject + Sized> Object for T {}
@@ -33,8 +313,16 @@ impl<T: RenderedObject> RenderedObject for Translated<T> {
         self.object.distance_to(point - self.translation)
     }
 
-    fn material_at(&self, point: Vec3) -> MaterialSample {
-        self.object.material_at(point - self.translation)
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(point - self.translation, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds().translated(self.translation)
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        self.object.gradient(point - self.translation)
     }
 }
 
@@ -48,8 +336,106 @@ impl<T: RenderedObject> RenderedObject for Scaled<T> {
         self.object.distance_to(point / self.scale) * self.scale
     }
 
-    fn material_at(&self, point: Vec3) -> MaterialSample {
-        self.object.material_at(point / self.scale)
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object.material_at(point / self.scale, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds().scaled(self.scale)
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        self.object.gradient(point / self.scale)
+    }
+}
+
+pub struct ScaledBy<T: RenderedObject> {
+    object: T,
+    scale: Vec3,
+    distance_factor: f32,
+}
+
+impl<T: RenderedObject> RenderedObject for ScaledBy<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(point / self.scale) * self.distance_factor
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object
+            .material_at(point / self.scale, (normal * self.scale).normalized())
+            .map_normal(|normal| normal / self.scale)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds().scaled(self.scale)
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        self.object
+            .gradient(point / self.scale)
+            .map(|gradient| gradient / self.scale)
+    }
+}
+
+pub struct Rotated<T: RenderedObject> {
+    object: T,
+    inverse_rotation: Quaternion,
+}
+
+impl<T: RenderedObject> RenderedObject for Rotated<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.inverse_rotation.rotate(point))
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object
+            .material_at(
+                self.inverse_rotation.rotate(point),
+                self.inverse_rotation.rotate(normal),
+            )
+            .map_normal(|normal| self.inverse_rotation.conjugate().rotate(normal))
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object
+            .bounds()
+            .rotated(self.inverse_rotation.conjugate())
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        self.object
+            .gradient(self.inverse_rotation.rotate(point))
+            .map(|gradient| self.inverse_rotation.conjugate().rotate(gradient))
+    }
+}
+
+pub struct Transformed<T: RenderedObject> {
+    object: T,
+    transform: Mat4,
+    inverse: Mat4,
+    /// The transformation can stretch space by different amounts in different directions, so
+    /// distances from the original object are multiplied by this to keep them conservative.
+    distance_factor: f32,
+}
+
+impl<T: RenderedObject> RenderedObject for Transformed<T> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        self.object.distance_to(self.inverse.transform_point(point)) * self.distance_factor
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.object
+            .material_at(
+                self.inverse.transform_point(point),
+                self.transform
+                    .transpose_transform_vector(normal)
+                    .normalized(),
+            )
+            .map_normal(|normal| self.inverse.transpose_transform_vector(normal))
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.object.bounds().transformed(&self.transform)
     }
 }
 
@@ -66,8 +452,16 @@ impl<M: Material> RenderedObject for Sphere<M> {
         point.magnitude() - 1.0
     }
 
-    fn material_at(&self, point: Vec3) -> MaterialSample {
-        self.mat.sample(point)
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(1)
+    }
+
+    fn gradient(&self, point: Vec3) -> Option<Vec3> {
+        Some(point)
     }
 }
 
@@ -86,10 +480,741 @@ pub fn cube<M: Material, T: Into<Vec3>>(mat: M, size: T) -> Cube<M> {
 impl<M: Material> RenderedObject for Cube<M> {
     fn distance_to(&self, point: Vec3) -> f32 {
         let face_distances = point.abs() - self.size;
-        face_distances.x.max(face_distances.y.max(face_distances.z))
+        // Outside the cube the closest point might be an edge or corner rather than a face.
+        face_distances.max(0).magnitude() + face_distances.max_component().min(0.0)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.size)
+    }
+}
+
+/// Turns a 2D distance into the distance to a shape made by stretching the 2D shape from
+/// -half_length to half_length along a third axis.
+fn extrude(distance_2d: f32, axis_pos: f32, half_length: f32) -> f32 {
+    let d = (distance_2d, axis_pos.abs() - half_length);
+    d.0.max(d.1).min(0.0) + (d.0.max(0.0).powi(2) + d.1.max(0.0).powi(2)).sqrt()
+}
+
+/// A donut lying flat in the XZ plane.
+pub struct Torus<M: Material> {
+    major_radius: f32,
+    minor_radius: f32,
+    mat: M,
+}
+
+/// major_radius is the distance from the center of the donut to the center of the ring,
+/// minor_radius is the thickness of the ring.
+pub fn torus<M: Material>(mat: M, major_radius: f32, minor_radius: f32) -> Torus<M> {
+    Torus {
+        major_radius,
+        minor_radius,
+        mat,
+    }
+}
+
+impl<M: Material> RenderedObject for Torus<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let ring_distance = Vec3::new(point.x, 0.0, point.z).magnitude() - self.major_radius;
+        Vec3::new(ring_distance, point.y, 0.0).magnitude() - self.minor_radius
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let outer = self.major_radius + self.minor_radius;
+        BoundingBox::centered((outer, self.minor_radius, outer))
+    }
+}
+
+/// Every point within radius of the line segment between start and end.
+pub struct Capsule<M: Material> {
+    start: Vec3,
+    end: Vec3,
+    radius: f32,
+    mat: M,
+}
+
+pub fn capsule<M: Material, T: Into<Vec3>, U: Into<Vec3>>(
+    mat: M,
+    start: T,
+    end: U,
+    radius: f32,
+) -> Capsule<M> {
+    Capsule {
+        start: start.into(),
+        end: end.into(),
+        radius,
+        mat,
+    }
+}
+
+/// An infinitely thin line between start and end.
+pub fn line_segment<M: Material, T: Into<Vec3>, U: Into<Vec3>>(
+    mat: M,
+    start: T,
+    end: U,
+) -> Capsule<M> {
+    capsule(mat, start, end, 0.0)
+}
+
+impl<M: Material> RenderedObject for Capsule<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let along = self.end - self.start;
+        let from_start = point - self.start;
+        let length_squared = along.dot(along);
+        let t = if length_squared > 0.0 {
+            (from_start.dot(along) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (from_start - along * t).magnitude() - self.radius
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::around_points(vec![self.start, self.end]).expanded(self.radius)
+    }
+}
+
+/// A cylinder pointing along the Y axis.
+pub struct Cylinder<M: Material> {
+    radius: f32,
+    half_height: f32,
+    mat: M,
+}
+
+/// The cylinder goes from -half_height to half_height along the Y axis.
+pub fn cylinder<M: Material>(mat: M, radius: f32, half_height: f32) -> Cylinder<M> {
+    Cylinder {
+        radius,
+        half_height,
+        mat,
+    }
+}
+
+/// A cylinder that goes on forever along the Y axis.
+pub fn infinite_cylinder<M: Material>(mat: M, radius: f32) -> Cylinder<M> {
+    cylinder(mat, radius, f32::INFINITY)
+}
+
+impl<M: Material> RenderedObject for Cylinder<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let circle_distance = Vec3::new(point.x, 0.0, point.z).magnitude() - self.radius;
+        if self.half_height.is_infinite() {
+            circle_distance
+        } else {
+            extrude(circle_distance, point.y, self.half_height)
+        }
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered((self.radius, self.half_height, self.radius))
+    }
+}
+
+/// A cone with its base centered on the origin and its tip pointing along +Y.
+pub struct Cone<M: Material> {
+    radius: f32,
+    height: f32,
+    mat: M,
+}
+
+pub fn cone<M: Material>(mat: M, radius: f32, height: f32) -> Cone<M> {
+    Cone {
+        radius,
+        height,
+        mat,
+    }
+}
+
+impl<M: Material> RenderedObject for Cone<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        // Work in 2D, with the tip at the origin and the edge of the base at q.
+        let q = Vec3::new(self.radius, -self.height, 0.0);
+        let w = Vec3::new(
+            Vec3::new(point.x, 0.0, point.z).magnitude(),
+            point.y - self.height,
+            0.0,
+        );
+        // Closest points on the slanted side and on the base.
+        let a = w - q * (w.dot(q) / q.dot(q)).clamp(0.0, 1.0);
+        let b = w - q * Vec3::new((w.x / q.x).clamp(0.0, 1.0), 1.0, 0.0);
+        let distance = a.dot(a).min(b.dot(b)).sqrt();
+        let side = (-(w.x * q.y - w.y * q.x)).max(-(w.y - q.y));
+        if side < 0.0 {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            (-self.radius, 0.0, -self.radius),
+            (self.radius, self.height, self.radius),
+        )
+    }
+}
+
+/// An infinite plane through the origin. Everything on the opposite side of the normal is
+/// solid.
+pub struct Plane<M: Material> {
+    normal: Vec3,
+    mat: M,
+}
+
+pub fn plane<M: Material, T: Into<Vec3>>(mat: M, normal: T) -> Plane<M> {
+    Plane {
+        normal: normal.into().normalized(),
+        mat,
+    }
+}
+
+impl<M: Material> RenderedObject for Plane<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        point.dot(self.normal)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn gradient(&self, _point: Vec3) -> Option<Vec3> {
+        Some(self.normal)
+    }
+}
+
+/// Like a cube, but with its edges and corners rounded off.
+pub struct RoundedBox<M: Material> {
+    size: Vec3,
+    radius: f32,
+    mat: M,
+}
+
+/// The box keeps the same overall size as cube(mat, size), radius only controls how much of
+/// the edges are rounded off.
+pub fn rounded_box<M: Material, T: Into<Vec3>>(mat: M, size: T, radius: f32) -> RoundedBox<M> {
+    RoundedBox {
+        size: size.into(),
+        radius,
+        mat,
+    }
+}
+
+impl<M: Material> RenderedObject for RoundedBox<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let face_distances = point.abs() - self.size + self.radius;
+        face_distances.max(0).magnitude() + face_distances.max_component().min(0.0) - self.radius
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.size)
+    }
+}
+
+/// A sphere stretched by a different amount along each axis.
+pub struct Ellipsoid<M: Material> {
+    radii: Vec3,
+    mat: M,
+}
+
+pub fn ellipsoid<M: Material, T: Into<Vec3>>(mat: M, radii: T) -> Ellipsoid<M> {
+    Ellipsoid {
+        radii: radii.into(),
+        mat,
+    }
+}
+
+/// Finds the root of the function Eberly describes in "Distance from a Point to an Ellipse, an
+/// Ellipsoid, or a Hyperellipsoid" using bisection. ratios and z should have the same length.
+fn ellipsoid_root(ratios: &[f64], z: &[f64], g: f64) -> f64 {
+    let mut n = [0.0; 3];
+    for ((n, r), z) in n.iter_mut().zip(ratios).zip(z) {
+        *n = r * z;
+    }
+    let n = &n[..z.len()];
+    let mut low = z[z.len() - 1] - 1.0;
+    let mut high = if g < 0.0 {
+        0.0
+    } else {
+        n.iter().map(|n| n * n).sum::<f64>().sqrt() - 1.0
+    };
+    let mut s = 0.0;
+    for _ in 0..200 {
+        s = (low + high) / 2.0;
+        if s == low || s == high {
+            break;
+        }
+        let g: f64 = n
+            .iter()
+            .zip(ratios)
+            .map(|(n, r)| (n / (s + r)).powi(2))
+            .sum::<f64>()
+            - 1.0;
+        if g > 0.0 {
+            low = s;
+        } else if g < 0.0 {
+            high = s;
+        } else {
+            break;
+        }
+    }
+    s
+}
+
+/// Takes an axis out of the first dims entries of an ellipsoid problem.
+fn remove_axis(radii: &mut [f64; 3], pos: &mut [f64; 3], dims: usize, axis: usize) {
+    radii.copy_within(axis + 1..dims, axis);
+    pos.copy_within(axis + 1..dims, axis);
+}
+
+/// Distance from a point to an ellipse or ellipsoid made of the first dims entries of radii and
+/// pos. radii must be sorted from biggest to smallest with no two the same, and pos must not
+/// have any negative components.
+fn distance_to_ellipsoid(mut radii: [f64; 3], mut pos: [f64; 3], mut dims: usize) -> f64 {
+    loop {
+        if dims == 1 {
+            return (pos[0] - radii[0]).abs();
+        }
+        let last = dims - 1;
+        if pos[last] > 0.0 {
+            if let Some(zero_axis) = pos[..last].iter().position(|&p| p <= 0.0) {
+                // The closest point is in the plane where that axis is zero, so solve the
+                // smaller problem instead.
+                remove_axis(&mut radii, &mut pos, dims, zero_axis);
+                dims -= 1;
+                continue;
+            }
+            let mut z = [0.0; 3];
+            let mut ratios = [0.0; 3];
+            for axis in 0..dims {
+                z[axis] = pos[axis] / radii[axis];
+                ratios[axis] = (radii[axis] / radii[last]).powi(2);
+            }
+            let g = z[..dims].iter().map(|z| z * z).sum::<f64>() - 1.0;
+            if g == 0.0 {
+                return 0.0;
+            }
+            let s = ellipsoid_root(&ratios[..dims], &z[..dims], g);
+            return pos[..dims]
+                .iter()
+                .zip(&ratios)
+                .map(|(p, r)| (r * p / (s + r) - p).powi(2))
+                .sum::<f64>()
+                .sqrt();
+        }
+        // The point is on the plane through the shortest axis. It might be closest to a point
+        // inside the ellipse where that plane cuts through, or to the edge of that ellipse.
+        let last_squared = radii[last] * radii[last];
+        let mut xde = [0.0; 3];
+        for axis in 0..last {
+            let r = radii[axis];
+            xde[axis] = r * pos[axis] / (r * r - last_squared);
+        }
+        let xde = &xde[..last];
+        let discriminant = 1.0 - xde.iter().map(|x| x * x).sum::<f64>();
+        if xde.iter().all(|&x| x < 1.0) && discriminant > 0.0 {
+            let in_plane: f64 = radii[..last]
+                .iter()
+                .zip(xde)
+                .zip(&pos)
+                .map(|((r, x), p)| (r * x - p).powi(2))
+                .sum();
+            return (in_plane + last_squared * discriminant).sqrt();
+        }
+        dims -= 1;
+    }
+}
+
+impl<M: Material> RenderedObject for Ellipsoid<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        let point = point.abs();
+        let mut axes = [
+            (self.radii.x as f64, point.x as f64),
+            (self.radii.y as f64, point.y as f64),
+            (self.radii.z as f64, point.z as f64),
+        ];
+        axes.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut radii: [f64; 3] = [0.0; 3];
+        let mut pos: [f64; 3] = [0.0; 3];
+        let mut dims = 0;
+        for (radius, p) in axes {
+            if dims > 0 && radius == radii[dims - 1] {
+                // The ellipsoid is round around two axes with the same radius, so only the
+                // distance from the other axes matters.
+                pos[dims - 1] = pos[dims - 1].hypot(p);
+            } else {
+                radii[dims] = radius;
+                pos[dims] = p;
+                dims += 1;
+            }
+        }
+        let distance = distance_to_ellipsoid(radii, pos, dims) as f32;
+        let inside = (point / self.radii).magnitude() < 1.0;
+        if inside {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.radii)
+    }
+}
+
+/// A hexagon in the XY plane stretched along the Z axis.
+pub struct HexagonalPrism<M: Material> {
+    radius: f32,
+    half_length: f32,
+    mat: M,
+}
+
+/// radius is the distance from the center to the middle of each side. The prism goes from
+/// -half_length to half_length along the Z axis.
+pub fn hexagonal_prism<M: Material>(mat: M, radius: f32, half_length: f32) -> HexagonalPrism<M> {
+    HexagonalPrism {
+        radius,
+        half_length,
+        mat,
+    }
+}
+
+impl<M: Material> RenderedObject for HexagonalPrism<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        const K: Vec3 = Vec3::new(-0.866_025_4, 0.5, 0.0);
+        const TAN_30: f32 = 0.577_350_3;
+        let mut p = point.abs();
+        p.z = 0.0;
+        // Fold the hexagon so we only need to check against one of its sides.
+        p -= K * 2.0 * K.dot(p).min(0.0);
+        let half_side = TAN_30 * self.radius;
+        let edge = Vec3::new(p.x.clamp(-half_side, half_side), self.radius, 0.0);
+        let distance = (p - edge).magnitude() * (p.y - self.radius).signum();
+        extrude(distance, point.z, self.half_length)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        // The corners are further from the center than the middle of the sides.
+        let corner_radius = self.radius * 2.0 / 3f32.sqrt();
+        BoundingBox::centered((corner_radius, corner_radius, self.half_length))
+    }
+}
+
+/// An equilateral triangle in the XY plane stretched along the Z axis.
+pub struct TriangularPrism<M: Material> {
+    radius: f32,
+    half_length: f32,
+    mat: M,
+}
+
+/// Each side of the triangle is twice radius long, with one side parallel to the X axis and the
+/// opposite corner pointing along +Y. The prism goes from -half_length to half_length along the
+/// Z axis.
+pub fn triangular_prism<M: Material>(mat: M, radius: f32, half_length: f32) -> TriangularPrism<M> {
+    TriangularPrism {
+        radius,
+        half_length,
+        mat,
+    }
+}
+
+impl<M: Material> RenderedObject for TriangularPrism<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        const SQRT_3: f32 = 1.732_050_8;
+        let r = self.radius;
+        let mut x = point.x.abs() - r;
+        let mut y = point.y + r / SQRT_3;
+        // Fold the triangle so we only need to check against one of its sides.
+        if x + SQRT_3 * y > 0.0 {
+            let (folded_x, folded_y) = ((x - SQRT_3 * y) / 2.0, (-SQRT_3 * x - y) / 2.0);
+            x = folded_x;
+            y = folded_y;
+        }
+        x -= x.clamp(-2.0 * r, 0.0);
+        let distance = -(x * x + y * y).sqrt() * y.signum();
+        extrude(distance, point.z, self.half_length)
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let r = self.radius;
+        BoundingBox::new(
+            (-r, -r / 3f32.sqrt(), -self.half_length),
+            (r, 2.0 * r / 3f32.sqrt(), self.half_length),
+        )
+    }
+}
+
+/// Two square pyramids glued together at their bases.
+pub struct Octahedron<M: Material> {
+    radius: f32,
+    mat: M,
+}
+
+/// radius is the distance from the center to each corner.
+pub fn octahedron<M: Material>(mat: M, radius: f32) -> Octahedron<M> {
+    Octahedron { radius, mat }
+}
+
+impl<M: Material> RenderedObject for Octahedron<M> {
+    fn distance_to(&self, point: Vec3) -> f32 {
+        const INV_SQRT_3: f32 = 0.577_350_3;
+        let p = point.abs();
+        let s = self.radius;
+        let m = p.sum() - s;
+        let q = if 3.0 * p.x < m {
+            p
+        } else if 3.0 * p.y < m {
+            Vec3::new(p.y, p.z, p.x)
+        } else if 3.0 * p.z < m {
+            Vec3::new(p.z, p.x, p.y)
+        } else {
+            // The closest point is on the face itself rather than an edge or corner.
+            return m * INV_SQRT_3;
+        };
+        let k = (0.5 * (q.z - q.y + s)).clamp(0.0, s);
+        Vec3::new(q.x, q.y - s + k, q.z - k).magnitude()
+    }
+
+    fn material_at(&self, point: Vec3, normal: Vec3) -> MaterialSample {
+        self.mat.sample(point, normal)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::centered(self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasicMaterial;
+
+    const SQRT_2: f32 = std::f32::consts::SQRT_2;
+    const INV_SQRT_3: f32 = 0.577_350_3;
+
+    fn assert_distance<T: RenderedObject, P: Into<Vec3>>(object: &T, point: P, expected: f32) {
+        let point = point.into();
+        let distance = object.distance_to(point);
+        assert!(
+            (distance - expected).abs() < 1e-4,
+            "distance to {:?} was {} instead of {}",
+            point,
+            distance,
+            expected
+        );
+    }
+
+    #[test]
+    fn cube_is_exact_at_edges() {
+        let cube = cube(BasicMaterial::default(), (1, 1, 1));
+        assert_distance(&cube, (0, 0, 0), -1.0);
+        assert_distance(&cube, (1, 0.5, 0), 0.0);
+        assert_distance(&cube, (3, 0, 0), 2.0);
+        assert_distance(&cube, (2, 2, 0), SQRT_2);
+    }
+
+    #[test]
+    fn torus() {
+        let torus = super::torus(BasicMaterial::default(), 2.0, 0.5);
+        assert_distance(&torus, (0, 0, 0), 1.5);
+        assert_distance(&torus, (2, 0, 0), -0.5);
+        assert_distance(&torus, (2.5, 0, 0), 0.0);
+        assert_distance(&torus, (2, 1, 0), 0.5);
+        assert_distance(&torus, (3, 0, 4), 2.5);
+    }
+
+    #[test]
+    fn capsule() {
+        let capsule = super::capsule(BasicMaterial::default(), (0, 0, 0), (0, 2, 0), 0.5);
+        assert_distance(&capsule, (0, 1, 0), -0.5);
+        assert_distance(&capsule, (0.5, 1, 0), 0.0);
+        assert_distance(&capsule, (0, 3, 0), 0.5);
+        assert_distance(&capsule, (1, -1, 0), SQRT_2 - 0.5);
+    }
+
+    #[test]
+    fn cylinder() {
+        let cylinder = super::cylinder(BasicMaterial::default(), 1.0, 2.0);
+        assert_distance(&cylinder, (0, 0, 0), -1.0);
+        assert_distance(&cylinder, (0, 1.5, 0), -0.5);
+        assert_distance(&cylinder, (1, 0, 0), 0.0);
+        assert_distance(&cylinder, (3, 0, 0), 2.0);
+        assert_distance(&cylinder, (0, 5, 0), 3.0);
+        assert_distance(&cylinder, (4, 6, 0), 5.0);
+        let infinite = infinite_cylinder(BasicMaterial::default(), 1.0);
+        assert_distance(&infinite, (0, 100, 0), -1.0);
+        assert_distance(&infinite, (0, 100, 3), 2.0);
+    }
+
+    #[test]
+    fn cone() {
+        let cone = super::cone(BasicMaterial::default(), 1.0, 1.0);
+        let side = 0.5 / SQRT_2;
+        assert_distance(&cone, (0, 0.5, 0), -side);
+        assert_distance(&cone, (0, 0, 0), 0.0);
+        assert_distance(&cone, (0, 2, 0), 1.0);
+        assert_distance(&cone, (0, -1, 0), 1.0);
+        assert_distance(&cone, (2, 0, 0), 1.0);
+        // Off the axis, facing the slanted side.
+        assert_distance(&cone, (1, 1, 0), 2.0 * side);
+        assert_distance(&cone, (0.6, 0.5, 0.8), side);
+        assert_distance(&cone, (0.3, 0.5, 0.4), 0.0);
+    }
+
+    #[test]
+    fn plane() {
+        let plane = super::plane(BasicMaterial::default(), (0, -1, 0));
+        assert_distance(&plane, (0, -3, 0), 3.0);
+        assert_distance(&plane, (4, 0, -2), 0.0);
+        assert_distance(&plane, (1, 2, 5), -2.0);
+    }
+
+    #[test]
+    fn rounded_box() {
+        let rounded = super::rounded_box(BasicMaterial::default(), (1, 1, 1), 0.25);
+        assert_distance(&rounded, (0, 0, 0), -1.0);
+        assert_distance(&rounded, (1, 0, 0), 0.0);
+        assert_distance(&rounded, (2, 0, 0), 1.0);
+        let corner = Vec3::from(0.75) + Vec3::from(0.25 * INV_SQRT_3);
+        assert_distance(&rounded, corner, 0.0);
+        assert_distance(&rounded, (2, 2, 2), 1.25 * 3f32.sqrt() - 0.25);
+    }
+
+    #[test]
+    fn ellipsoid() {
+        let ellipsoid = super::ellipsoid(BasicMaterial::default(), (3, 2, 1));
+        assert_distance(&ellipsoid, (0, 0, 0), -1.0);
+        assert_distance(&ellipsoid, (3, 0, 0), 0.0);
+        assert_distance(&ellipsoid, (5, 0, 0), 2.0);
+        assert_distance(&ellipsoid, (0, -4, 0), 2.0);
+        assert_distance(&ellipsoid, (0, 0, 3), 2.0);
+        // Moving away from a point on the surface along its normal, which needs the root
+        // solver since the point isn't on any of the axes.
+        let surface = Vec3::new(2.0, 4.0 / 3.0, 1.0 / 3.0);
+        let normal = (surface / Vec3::new(9.0, 4.0, 1.0)).normalized();
+        assert_distance(&ellipsoid, surface, 0.0);
+        assert_distance(&ellipsoid, surface + normal * 0.5, 0.5);
+        assert_distance(&ellipsoid, (surface + normal * 0.5) * -1, 0.5);
+        assert_distance(&ellipsoid, surface - normal * 0.1, -0.1);
+    }
+
+    #[test]
+    fn ellipsoid_with_equal_radii() {
+        let sphere = super::ellipsoid(BasicMaterial::default(), (2, 2, 2));
+        assert_distance(&sphere, (0, 0, 0), -2.0);
+        assert_distance(&sphere, (3, 4, 0), 3.0);
+        assert_distance(&sphere, (1, 1, 1), 3f32.sqrt() - 2.0);
+
+        let oblate = super::ellipsoid(BasicMaterial::default(), (2, 2, 1));
+        assert_distance(&oblate, (0, 0, 0), -1.0);
+        assert_distance(&oblate, (3, 0, 0), 1.0);
+        assert_distance(&oblate, (0, 0, -3), 2.0);
+        // On the plane through the short axis, where the closest point is off the axes.
+        assert_distance(&oblate, (0.5, 0.5, 0), -(5f32 / 6.0).sqrt());
+        let surface = Vec3::new(1.0, 1.0, 0.5f32.sqrt());
+        let normal = (surface / Vec3::new(4.0, 4.0, 1.0)).normalized();
+        assert_distance(&oblate, surface, 0.0);
+        assert_distance(&oblate, surface + normal * 0.5, 0.5);
+        assert_distance(&oblate, surface - normal * 0.1, -0.1);
+
+        let prolate = super::ellipsoid(BasicMaterial::default(), (3, 1, 1));
+        assert_distance(&prolate, (4, 0, 0), 1.0);
+        assert_distance(&prolate, (0, 2, 2), 2.0 * SQRT_2 - 1.0);
+        let surface = Vec3::new(1.8, 0.4 * SQRT_2, 0.4 * SQRT_2);
+        let normal = (surface / Vec3::new(9.0, 1.0, 1.0)).normalized();
+        assert_distance(&prolate, surface, 0.0);
+        assert_distance(&prolate, surface + normal * 0.5, 0.5);
+        assert_distance(&prolate, surface - normal * 0.1, -0.1);
+    }
+
+    #[test]
+    fn hexagonal_prism() {
+        let prism = super::hexagonal_prism(BasicMaterial::default(), 1.0, 2.0);
+        assert_distance(&prism, (0, 0, 0), -1.0);
+        assert_distance(&prism, (0, 1, 0), 0.0);
+        assert_distance(&prism, (0, 3, 0), 2.0);
+        assert_distance(&prism, (0, 0, 5), 3.0);
+        // Past a corner, and in front of one of the slanted sides.
+        assert_distance(&prism, (2, 0, 0), 2.0 - 2.0 * INV_SQRT_3);
+        assert_distance(&prism, (3f32.sqrt(), 1, 0), 1.0);
+    }
+
+    #[test]
+    fn triangular_prism() {
+        let prism = super::triangular_prism(BasicMaterial::default(), 1.0, 2.0);
+        assert_distance(&prism, (0, 0, 0), -INV_SQRT_3);
+        assert_distance(&prism, (0, -INV_SQRT_3, 0), 0.0);
+        assert_distance(&prism, (0, -1, 0), 1.0 - INV_SQRT_3);
+        assert_distance(&prism, (0, 2, 0), 2.0 - 2.0 * INV_SQRT_3);
+        assert_distance(&prism, (0, 0, 3), 1.0);
+    }
+
+    #[test]
+    fn octahedron() {
+        let octahedron = super::octahedron(BasicMaterial::default(), 1.0);
+        assert_distance(&octahedron, (0, 0, 0), -INV_SQRT_3);
+        assert_distance(&octahedron, (1, 0, 0), 0.0);
+        assert_distance(&octahedron, (2, 0, 0), 1.0);
+        assert_distance(&octahedron, (1, 1, 1), 2.0 * INV_SQRT_3);
+        assert_distance(&octahedron, (1, 1, 0), 0.5 * SQRT_2);
+    }
+
+    #[test]
+    fn scaled_by() {
+        let mirrored = sphere(BasicMaterial::default())
+            .scaled_by((-2, 2, 2))
+            .unwrap();
+        assert_distance(&mirrored, (-5, 0, 0), 3.0);
+        assert_distance(&mirrored, (5, 0, 0), 3.0);
+        assert!(sphere(BasicMaterial::default())
+            .scaled_by((1, 0, 1))
+            .is_none());
+        assert!(sphere(BasicMaterial::default())
+            .scaled_by((1, f32::NAN, 1))
+            .is_none());
     }
 
-    fn material_at(&self, point: Vec3) -> MaterialSample {
-        self.mat.sample(point)
+    #[test]
+    fn rounded() {
+        let rounded = cube(BasicMaterial::default(), (0.7, 0.7, 0.7)).rounded(0.3);
+        assert_distance(&rounded, (0, 0, 0), -1.0);
+        assert_distance(&rounded, (1, 0.5, 0), 0.0);
+        // The corner is cut off by a sphere around the corner of the smaller cube.
+        assert_distance(&rounded, (1, 1, 1), 0.3 * 3f32.sqrt() - 0.3);
     }
 }
@@ -1,6 +1,6 @@
 use crate::Vec3;
 
-pub trait PostProcessor {
+pub trait PostProcessor: Send + Sync {
     fn process_pixel(&self, pixel: Vec3) -> Vec3;
 }
 
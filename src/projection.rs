@@ -0,0 +1,106 @@
+use crate::Vec3;
+use std::f32::consts::PI;
+
+/// Decides which ray each position on the image corresponds to.
+pub trait Projection: Send + Sync {
+    /// x and y describe a position on the image, where (-1, -1) is the bottom left corner and
+    /// (1, 1) is the top right corner. Returns the origin and direction of the ray in camera
+    /// space, where +X is right, +Y is up, and +Z is forward. The direction does not need to be
+    /// normalized. Returns None if nothing is visible at that position.
+    fn project(&self, x: f32, y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)>;
+}
+
+/// A regular pinhole camera where straight lines stay straight.
+#[derive(Clone, Debug)]
+pub struct Perspective {
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+}
+
+impl Projection for Perspective {
+    fn project(&self, x: f32, y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let half_height = (self.fov.to_radians() / 2.0).tan();
+        let direction = (x * aspect_ratio * half_height, y * half_height, 1);
+        Some((0.into(), direction.into()))
+    }
+}
+
+/// All rays are parallel, so objects do not get smaller as they get further away.
+#[derive(Clone, Debug)]
+pub struct Orthographic {
+    /// How much of the scene is visible vertically, in world units.
+    pub height: f32,
+}
+
+impl Projection for Orthographic {
+    fn project(&self, x: f32, y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let origin = (
+            x * aspect_ratio * self.height / 2.0,
+            y * self.height / 2.0,
+            0,
+        );
+        Some((origin.into(), (0, 0, 1).into()))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    /// Distance from the center of the image is proportional to the angle from the forward
+    /// direction.
+    Equidistant,
+    /// Every part of the image covers the same solid angle.
+    Equisolid,
+}
+
+#[derive(Clone, Debug)]
+pub struct Fisheye {
+    /// How many degrees are visible from the top of the image to the bottom. Can go up to 360.
+    pub fov: f32,
+    pub mapping: FisheyeMapping,
+}
+
+impl Projection for Fisheye {
+    fn project(&self, x: f32, y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let x = x * aspect_ratio;
+        let radius = (x * x + y * y).sqrt();
+        let max_angle = self.fov.to_radians() / 2.0;
+        let angle = match self.mapping {
+            FisheyeMapping::Equidistant => radius * max_angle,
+            FisheyeMapping::Equisolid => {
+                let sin_half_angle = radius * (max_angle / 2.0).sin();
+                if sin_half_angle > 1.0 {
+                    return None;
+                }
+                2.0 * sin_half_angle.asin()
+            }
+        };
+        if angle > PI {
+            return None;
+        }
+        let around = y.atan2(x);
+        let direction = (
+            angle.sin() * around.cos(),
+            angle.sin() * around.sin(),
+            angle.cos(),
+        );
+        Some((0.into(), direction.into()))
+    }
+}
+
+/// Covers every direction, with longitude along the X axis and latitude along the Y axis. The
+/// image should be twice as wide as it is tall to avoid stretching.
+#[derive(Clone, Debug)]
+pub struct Equirectangular;
+
+impl Projection for Equirectangular {
+    fn project(&self, x: f32, y: f32, _aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let longitude = x * PI;
+        let latitude = y * PI / 2.0;
+        let direction = (
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        );
+        Some((0.into(), direction.into()))
+    }
+}
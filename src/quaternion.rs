@@ -0,0 +1,94 @@
+use crate::Vec3;
+use std::ops::Mul;
+
+/// Represents a rotation in 3D space.
+#[derive(Clone, Copy, Debug)]
+pub struct Quaternion {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Quaternion {
+    pub const fn identity() -> Self {
+        Self {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// Returns a rotation of the given number of degrees counterclockwise around the given axis.
+    /// The axis does not need to be normalized.
+    pub fn from_axis_angle<V: Into<Vec3>>(axis: V, degrees: f32) -> Self {
+        let axis = axis.into().normalized();
+        let half_angle = degrees.to_radians() / 2.0;
+        let sin = half_angle.sin();
+        Self {
+            w: half_angle.cos(),
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+        }
+    }
+
+    /// Returns a rotation that first rotates around the X axis, then the Y axis, then the Z axis
+    /// by the corresponding number of degrees.
+    pub fn from_euler<V: Into<Vec3>>(degrees: V) -> Self {
+        let degrees = degrees.into();
+        Self::from_axis_angle((0, 0, 1), degrees.z)
+            * Self::from_axis_angle((0, 1, 0), degrees.y)
+            * Self::from_axis_angle((1, 0, 0), degrees.x)
+    }
+
+    /// Returns the opposite rotation, assuming the quaternion is normalized.
+    pub fn conjugate(self) -> Self {
+        Self {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    pub fn normalized(self) -> Self {
+        let magnitude =
+            (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        Self {
+            w: self.w / magnitude,
+            x: self.x / magnitude,
+            y: self.y / magnitude,
+            z: self.z / magnitude,
+        }
+    }
+
+    pub fn rotate<V: Into<Vec3>>(self, v: V) -> Vec3 {
+        let v = v.into();
+        let axis = Vec3::new(self.x, self.y, self.z);
+        let t = axis.cross(v) * 2.0;
+        v + t * self.w + axis.cross(t)
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Multiplying two quaternions produces a rotation that does the right hand side rotation first
+/// and then the left hand side rotation.
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, other: Quaternion) -> Quaternion {
+        Quaternion {
+            w: self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+            x: self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            y: self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            z: self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+        }
+    }
+}
@@ -1,44 +1,154 @@
-use crate::{PostProcessor, Scene, Vec3};
+use crate::{
+    wavelength_to_rgb, Camera, PostProcessor, Scene, Tracer, Vec3, MAX_WAVELENGTH, MIN_WAVELENGTH,
+};
 use image::{ImageBuffer, RgbImage};
+use rand::Rng;
 use rand_distr::{Distribution, Normal};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub struct Renderer<P: PostProcessor> {
-    pub size: u32,
+    pub camera: Camera,
     pub samples: u32,
     pub num_bounces: u32,
-    pub camera_size: f32,
     pub pixel_size: f32,
+    /// How many threads to render with. Zero uses one thread per available core.
+    pub threads: u32,
+    /// The image is split into square tiles of this many pixels on each side. Threads pick up
+    /// the next unrendered tile whenever they finish one, so smaller tiles balance the load
+    /// better when some parts of the image are much more expensive than others.
+    pub tile_size: u32,
+    pub tracer: Tracer,
+    /// Gives every sample a single wavelength of light instead of treating light as red, green
+    /// and blue, which lets transparent materials with dispersion split light into rainbows.
+    /// Needs more samples for the colors to stop looking noisy.
+    pub spectral: bool,
     pub post_process: P,
 }
 
+#[derive(Clone, Copy, Debug)]
+struct Tile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
 impl<P: PostProcessor> Renderer<P> {
-    fn sample(&self, scene: &Scene, x: u32, y: u32) -> Vec3 {
+    fn sample(&self, scene: &Scene, x: u32, y: u32, wavelength: Option<f32>) -> Vec3 {
         let mut rng = rand::thread_rng();
         let dist = Normal::new(0.0, self.pixel_size / 2.0).unwrap();
         let dx = dist.sample(&mut rng);
         let dy = dist.sample(&mut rng);
-        let ray_dir_x = (((x as f32 + dx) / self.size as f32) - 0.5) * 2.0 * self.camera_size;
-        let ray_dir_y = (((y as f32 + dy) / self.size as f32) - 0.5) * 2.0 * self.camera_size;
-        let ray_dir_z = 1.0;
-        let ray_dir = Vec3::new(ray_dir_x, ray_dir_y, ray_dir_z).normalized();
-        scene.do_camera_ray(0.into(), ray_dir, self.num_bounces)
+        let (origin, direction) = match self.camera.primary_ray(x as f32 + dx, y as f32 + dy) {
+            Some(ray) => ray,
+            None => return 0.into(),
+        };
+        let light = scene.do_camera_ray(
+            &self.tracer,
+            origin,
+            direction,
+            wavelength,
+            self.num_bounces,
+        );
+        match wavelength {
+            // Only light of this wavelength made it back, so only the part of the color it
+            // contributes to counts.
+            Some(wavelength) => light * wavelength_to_rgb(wavelength),
+            None => light,
+        }
+    }
+
+    fn render_pixel(&self, scene: &Scene, x: u32, y: u32) -> [u8; 3] {
+        let mut rng = rand::thread_rng();
+        let mut color: Vec3 = 0.into();
+        for index in 0..self.samples {
+            // Spread the wavelengths evenly over the samples so that every part of the spectrum
+            // gets covered.
+            let wavelength = if self.spectral {
+                let t = (index as f32 + rng.gen_range(0.0, 1.0)) / self.samples as f32;
+                Some(MIN_WAVELENGTH + t * (MAX_WAVELENGTH - MIN_WAVELENGTH))
+            } else {
+                None
+            };
+            color += self.sample(scene, x, y, wavelength);
+        }
+        color /= self.samples as f32;
+        color = self.post_process.process_pixel(color);
+        [
+            (color.x * 255.0) as u8,
+            (color.y * 255.0) as u8,
+            (color.z * 255.0) as u8,
+        ]
+    }
+
+    /// Returns the pixels of the tile in row-major order.
+    fn render_tile(&self, scene: &Scene, tile: Tile) -> Vec<[u8; 3]> {
+        let mut pixels = Vec::with_capacity((tile.width * tile.height) as usize);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                pixels.push(self.render_pixel(scene, x, y));
+            }
+        }
+        pixels
+    }
+
+    fn tiles(&self) -> Vec<Tile> {
+        let tile_size = self.tile_size.max(1);
+        let (width, height) = (self.camera.width, self.camera.height);
+        let mut tiles = Vec::new();
+        for y in (0..height).step_by(tile_size as usize) {
+            for x in (0..width).step_by(tile_size as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    fn num_threads(&self) -> usize {
+        if self.threads == 0 {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        } else {
+            self.threads as usize
+        }
     }
 
     pub fn render(&self, scene: &Scene, filename: &str) {
-        let mut buf: RgbImage = ImageBuffer::new(self.size, self.size);
-        for x in 0..self.size {
-            for y in 0..self.size {
-                let mut color: Vec3 = 0.into();
-                for _ in 0..self.samples {
-                    color += self.sample(scene, x, y);
-                }
-                color /= self.samples as f32;
-                color = self.post_process.process_pixel(color);
-                let color = [
-                    (color.x * 255.0) as u8,
-                    (color.y * 255.0) as u8,
-                    (color.z * 255.0) as u8,
-                ];
+        let mut buf: RgbImage = ImageBuffer::new(self.camera.width, self.camera.height);
+        let tiles = self.tiles();
+        let next_tile = AtomicUsize::new(0);
+        let num_threads = self.num_threads().min(tiles.len()).max(1);
+        let rendered_tiles: Vec<(Tile, Vec<[u8; 3]>)> = std::thread::scope(|s| {
+            let workers: Vec<_> = (0..num_threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut finished = Vec::new();
+                        loop {
+                            let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                            let tile = match tiles.get(index) {
+                                Some(tile) => *tile,
+                                None => break finished,
+                            };
+                            finished.push((tile, self.render_tile(scene, tile)));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        for (tile, pixels) in rendered_tiles {
+            for (index, color) in pixels.into_iter().enumerate() {
+                let x = tile.x + index as u32 % tile.width;
+                let y = tile.y + index as u32 / tile.width;
                 buf.put_pixel(x, y, color.into());
             }
         }
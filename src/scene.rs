@@ -1,114 +1,612 @@
-use crate::{ImmediateLight, RenderedObject, Vec3, MAX_SDF_DISTANCE, MIN_SDF_DISTANCE};
+use crate::{
+    cosine_direction, facing_viewer, henyey_greenstein, reflect_or_refract,
+    sample_henyey_greenstein, track_through_media, transmittance_through_media, Bvh,
+    ImmediateLight, Lobe, MaterialSample, Medium, MediumEvent, NormalMethod, RenderedObject,
+    Tracer, Vec3, MAX_SDF_DISTANCE,
+};
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Describes where a ray hit something.
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    pub position: Vec3,
+    /// Direction the ray was travelling in.
+    pub direction: Vec3,
+    /// Index of the object that was hit, counting in the order objects were added to the scene.
+    pub object: usize,
+    /// How far the ray travelled before hitting the object.
+    pub distance: f32,
+    /// How many steps the ray took before hitting the object.
+    pub steps: u32,
+}
+
+/// What to do with a ray that takes too many steps without hitting anything or flying off into
+/// the sky. This usually happens when a ray skims along a surface.
+#[derive(Clone, Copy, Debug)]
+pub enum OutOfSteps {
+    /// Pretend the ray flew off into the sky. Shadow rays count as reaching their light.
+    Miss,
+    /// Pretend the ray hit whatever it is closest to. Shadow rays count as blocked.
+    Hit,
+    /// Show a solid color so that problem areas are easy to spot. Shadow rays count as blocked.
+    Color(Vec3),
+}
+
+/// How many rays were stopped by each limit since the counts were last reset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LimitCounts {
+    pub max_steps: u64,
+    pub max_distance: u64,
+}
+
+enum MarchResult {
+    Hit(Hit),
+    Miss,
+    /// The ray ran out of steps, contains the closest object to where it stopped.
+    OutOfSteps(Option<Hit>),
+}
 
 pub struct Scene {
     objects: Vec<Box<dyn RenderedObject>>,
     lights: Vec<Box<dyn ImmediateLight>>,
+    media: Vec<Box<dyn Medium>>,
     sky_color: Vec3,
+    /// Built the first time the scene is rendered, and thrown away whenever objects are added
+    /// or it is turned on or off.
+    bvh: OnceLock<Bvh>,
+    use_bvh: bool,
+    max_steps: u32,
+    max_distance: f32,
+    out_of_steps: OutOfSteps,
+    normal_method: NormalMethod,
+    max_steps_count: AtomicU64,
+    max_distance_count: AtomicU64,
 }
 
-impl Scene {
-    pub fn new() -> Self {
+impl Default for Scene {
+    fn default() -> Self {
         Self {
             objects: vec![],
             lights: vec![],
+            media: vec![],
             sky_color: (0, 0, 1).into(),
+            bvh: OnceLock::new(),
+            use_bvh: true,
+            max_steps: 1000,
+            max_distance: MAX_SDF_DISTANCE,
+            out_of_steps: OutOfSteps::Miss,
+            normal_method: NormalMethod::default(),
+            max_steps_count: AtomicU64::new(0),
+            max_distance_count: AtomicU64::new(0),
         }
     }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
     pub fn add_object<T: RenderedObject + 'static>(&mut self, obj: T) {
         self.objects.push(Box::new(obj));
+        self.bvh = OnceLock::new();
     }
 
     pub fn add_light<T: ImmediateLight + 'static>(&mut self, obj: T) {
         self.lights.push(Box::new(obj));
     }
 
+    /// Adds fog, smoke or anything else which light can scatter off of on its way through the
+    /// scene.
+    pub fn add_medium<T: Medium + 'static>(&mut self, medium: T) {
+        self.media.push(Box::new(medium));
+    }
+
     pub fn set_sky_color<T: Into<Vec3>>(&mut self, color: T) {
         self.sky_color = color.into();
     }
 
-    fn distance_field_at(&self, point: Vec3) -> f32 {
-        let mut value = std::f32::MAX;
-        for obj in &self.objects {
-            value = value.min(obj.distance_to(point));
+    /// Turns skipping distant objects with a bounding volume hierarchy on or off. With it off,
+    /// every object is checked at every step, which is only useful for measuring how much the
+    /// hierarchy helps.
+    pub fn set_use_bvh(&mut self, use_bvh: bool) {
+        self.use_bvh = use_bvh;
+        self.bvh = OnceLock::new();
+    }
+
+    /// Sets how many steps a ray can take before giving up. See set_out_of_steps for what
+    /// happens to rays that give up.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Rays which travel further than this without hitting anything show the sky.
+    pub fn set_max_distance(&mut self, max_distance: f32) {
+        self.max_distance = max_distance;
+    }
+
+    pub fn set_out_of_steps(&mut self, out_of_steps: OutOfSteps) {
+        self.out_of_steps = out_of_steps;
+    }
+
+    /// Sets how normals are estimated for objects which can't work them out exactly.
+    pub fn set_normal_method(&mut self, normal_method: NormalMethod) {
+        self.normal_method = normal_method;
+    }
+
+    pub fn limit_counts(&self) -> LimitCounts {
+        LimitCounts {
+            max_steps: self.max_steps_count.load(Ordering::Relaxed),
+            max_distance: self.max_distance_count.load(Ordering::Relaxed),
         }
-        value
     }
 
-    fn normal_at(&self, point: Vec3) -> Vec3 {
-        const EPSILON: f32 = 1e-4;
-        let df_center = self.distance_field_at(point);
-        let df_x = self.distance_field_at(point + (EPSILON, 0, 0));
-        let df_y = self.distance_field_at(point + (0, EPSILON, 0));
-        let df_z = self.distance_field_at(point + (0, 0, EPSILON));
-        Vec3::from((df_x - df_center, df_y - df_center, df_z - df_center)).normalized()
+    pub fn reset_limit_counts(&self) {
+        self.max_steps_count.store(0, Ordering::Relaxed);
+        self.max_distance_count.store(0, Ordering::Relaxed);
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            if self.use_bvh {
+                Bvh::build(&self.objects)
+            } else {
+                Bvh::linear(&self.objects)
+            }
+        })
+    }
+
+    fn distance_field_at(&self, point: Vec3) -> f32 {
+        self.bvh().distance_to(&self.objects, point).0
     }
 
-    fn march_can_reach(&self, ray_start: Vec3, target: Vec3) -> bool {
-        let mut remaining_len = (target - ray_start).magnitude();
+    /// Returns how much of the light travelling from target makes it to ray_start, which is
+    /// none of it if something is in the way.
+    fn light_reaching(&self, tracer: &Tracer, ray_start: Vec3, target: Vec3) -> Vec3 {
+        if !self.march_can_reach(tracer, ray_start, target) {
+            return 0.into();
+        }
+        if self.media.is_empty() {
+            return 1.into();
+        }
+        let length = (target - ray_start).magnitude().min(self.max_distance);
         let dir = (target - ray_start).normalized();
-        let mut pos = ray_start;
-        loop {
+        transmittance_through_media(&self.media, ray_start, dir, length)
+    }
+
+    /// Returns whether a ray from ray_start gets to target without hitting anything. Shadow rays
+    /// follow the same step and distance limits as camera rays and count towards limit_counts.
+    fn march_can_reach(&self, tracer: &Tracer, ray_start: Vec3, target: Vec3) -> bool {
+        let length = (target - ray_start).magnitude();
+        let dir = (target - ray_start).normalized();
+        let mut travelled = 0.0;
+        for _ in 0..self.max_steps {
+            let pos = ray_start + dir * travelled;
             let df = self.distance_field_at(pos);
-            if df <= MIN_SDF_DISTANCE {
+            if df <= tracer.hit_distance_at(travelled, pos) {
                 return false;
+            } else if df >= MAX_SDF_DISTANCE {
+                return true;
             }
-            remaining_len -= df;
-            if remaining_len <= 0.0 {
+            travelled += df;
+            if travelled >= length {
+                return true;
+            }
+            // Treat shadow rays the same as camera rays, which count as flying off into the sky
+            // once they go too far.
+            if travelled > self.max_distance {
+                self.max_distance_count.fetch_add(1, Ordering::Relaxed);
                 return true;
             }
-            pos += dir * df;
         }
+        self.max_steps_count.fetch_add(1, Ordering::Relaxed);
+        matches!(self.out_of_steps, OutOfSteps::Miss)
     }
 
-    fn march_until_hit(&self, ray_start: Vec3, ray_dir: Vec3) -> Option<Vec3> {
+    fn march(&self, tracer: &Tracer, ray_start: Vec3, ray_dir: Vec3) -> MarchResult {
+        let mut relaxation = tracer.relaxation;
+        let mut travelled = 0.0;
+        let mut last_df = 0.0;
+        let mut last_step = 0.0;
         let mut pos = ray_start;
-        loop {
-            let df = self.distance_field_at(pos);
-            if df <= MIN_SDF_DISTANCE {
-                return Some(pos);
+        for steps in 1..=self.max_steps {
+            pos = ray_start + ray_dir * travelled;
+            let (df, object) = self.bvh().distance_to(&self.objects, pos);
+            if relaxation > 1.0 && df + last_df < last_step {
+                // The spheres around this point and the last one don't overlap, so the last step
+                // might have jumped over a surface. Go back and take a normal step instead, and
+                // don't take any more risks for the rest of the ray.
+                travelled += last_df - last_step;
+                last_step = last_df;
+                relaxation = 1.0;
+                continue;
+            }
+            if df <= tracer.hit_distance_at(travelled, pos) {
+                // The distance can only come from a bounding box instead of an object when the
+                // box is tiny, in which case look up whatever is closest.
+                let object = match object.or_else(|| self.closest_object(pos)) {
+                    Some(object) => object,
+                    None => return MarchResult::Miss,
+                };
+                return MarchResult::Hit(Hit {
+                    position: pos,
+                    direction: ray_dir,
+                    object,
+                    distance: travelled,
+                    steps,
+                });
             } else if df >= MAX_SDF_DISTANCE {
-                return None;
+                return MarchResult::Miss;
+            }
+            last_df = df;
+            last_step = df * relaxation;
+            travelled += last_step;
+            if travelled > self.max_distance {
+                self.max_distance_count.fetch_add(1, Ordering::Relaxed);
+                return MarchResult::Miss;
             }
-            pos += ray_dir * df;
         }
+        self.max_steps_count.fetch_add(1, Ordering::Relaxed);
+        MarchResult::OutOfSteps(self.closest_object(pos).map(|object| Hit {
+            position: pos,
+            direction: ray_dir,
+            object,
+            distance: travelled,
+            steps: self.max_steps,
+        }))
     }
 
-    fn color_on_surface(&self, surface_pos: Vec3, remaining_bounces: u32) -> Vec3 {
-        let mut result: Vec3 = 0.into();
-        let normal = self.normal_at(surface_pos);
-        let ray_start = surface_pos + normal * MIN_SDF_DISTANCE * 2.0;
-        let mat = self
-            .objects
-            .iter()
-            .find(|o| o.distance_to(surface_pos) <= MIN_SDF_DISTANCE)
-            .unwrap()
-            .material_at(surface_pos);
-        let surface_color = mat.base_color;
-        result += mat.emission;
+    fn closest_object(&self, point: Vec3) -> Option<usize> {
+        self.bvh()
+            .closest(&self.objects, point)
+            .map(|(object, _)| object)
+    }
+
+    /// Returns where the ray first hits something, or None if it flies off into the sky.
+    pub fn march_until_hit(&self, tracer: &Tracer, ray_start: Vec3, ray_dir: Vec3) -> Option<Hit> {
+        match self.march(tracer, ray_start, ray_dir) {
+            MarchResult::Hit(hit) => Some(hit),
+            MarchResult::Miss => None,
+            MarchResult::OutOfSteps(hit) => match self.out_of_steps {
+                OutOfSteps::Hit => hit,
+                _ => None,
+            },
+        }
+    }
+
+    fn color_on_surface(
+        &self,
+        tracer: &Tracer,
+        hit: Hit,
+        wavelength: Option<f32>,
+        remaining_bounces: u32,
+    ) -> Vec3 {
+        let surface_pos = hit.position;
+        let normal = self
+            .normal_method
+            .normal_at(self.objects[hit.object].as_ref(), surface_pos);
+        let to_viewer = hit.direction * -1;
+        // Start far enough away from the surface that new rays don't immediately hit it again.
+        let ray_start =
+            surface_pos + normal * tracer.hit_distance_at(hit.distance, surface_pos) * 2.0;
+        let mat = self.objects[hit.object].material_at(surface_pos, normal);
+        let mut result = mat.emission;
+        if mat.transmission > 0.0 && rand::thread_rng().gen_range(0.0, 1.0) < mat.transmission {
+            if remaining_bounces > 0 {
+                result += self.color_through_surface(
+                    tracer,
+                    hit,
+                    &mat,
+                    normal,
+                    wavelength,
+                    remaining_bounces - 1,
+                );
+            }
+            return result;
+        }
+        // Lighting uses the material's shading normal, but rays still have to leave from the
+        // right side of the real surface.
+        let shading_normal = match mat.normal {
+            Some(shading_normal) => facing_viewer(shading_normal, to_viewer),
+            None => normal,
+        };
         for light in &self.lights {
             let sample = light.sample(ray_start);
-            let brightness = (sample.shadow_ray_target - ray_start)
-                .normalized()
-                .dot(normal);
-            if brightness > 0.0 && self.march_can_reach(ray_start, sample.shadow_ray_target) {
-                result += sample.color * surface_color * brightness;
+            let to_light = (sample.shadow_ray_target - ray_start).normalized();
+            let reflectance = mat.reflectance(shading_normal, to_viewer, to_light);
+            if reflectance.max_component() > 0.0 && normal.dot(to_light) > 0.0 {
+                result += sample.color
+                    * reflectance
+                    * self.light_reaching(tracer, ray_start, sample.shadow_ray_target);
             }
         }
+        // Follow just one of the ways light can bounce off the surface.
+        if let Some((lobe, chance)) = mat.pick_lobe(shading_normal, to_viewer) {
+            if lobe == Lobe::Subsurface {
+                let subsurface = mat.subsurface_fraction(shading_normal, to_viewer);
+                result += self.color_under_surface(
+                    tracer,
+                    hit,
+                    &mat,
+                    normal,
+                    wavelength,
+                    remaining_bounces,
+                ) * subsurface
+                    / chance;
+            } else if remaining_bounces > 0 {
+                if let Some((dir, weight)) = mat.sample_bounce(lobe, shading_normal, to_viewer) {
+                    if normal.dot(dir) > 0.0 {
+                        let light = self.do_camera_ray(
+                            tracer,
+                            ray_start,
+                            dir,
+                            wavelength,
+                            remaining_bounces - 1,
+                        );
+                        result += light * weight;
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the light which comes back out of a translucent object towards where a ray hit
+    /// it, after entering the object somewhere and wandering around under the surface. Uses a
+    /// random walk, which follows light scattering around inside the object until it happens to
+    /// reach the surface again, with spectral tracking for colors like the media use.
+    fn color_under_surface(
+        &self,
+        tracer: &Tracer,
+        hit: Hit,
+        mat: &MaterialSample,
+        normal: Vec3,
+        wavelength: Option<f32>,
+        remaining_bounces: u32,
+    ) -> Vec3 {
+        // Light which scatters this many times has almost certainly been absorbed.
+        const MAX_SCATTERS: u32 = 1024;
+        let mut rng = rand::thread_rng();
+        let object = self.objects[hit.object].as_ref();
+        let extinction = Vec3::from(1) / mat.mean_free_path.max(1e-6);
+        let scattering = extinction * mat.subsurface_albedo;
+        let transmittance = |distance: f32| {
+            Vec3::new(
+                (-extinction.x * distance).exp(),
+                (-extinction.y * distance).exp(),
+                (-extinction.z * distance).exp(),
+            )
+        };
+        let offset = tracer.inside_offset(&hit);
+        let mut pos = hit.position - normal * offset;
+        let mut dir = cosine_direction(normal * -1);
+        let mut weight: Vec3 = 1.into();
+        for _ in 0..MAX_SCATTERS {
+            // Pick which color's mean free path to step by, favoring the colors which still
+            // carry the most light.
+            let mut choice = rng.gen_range(0.0, weight.sum());
+            let mut chosen = extinction.z;
+            for &(channel_weight, channel_extinction) in
+                &[(weight.x, extinction.x), (weight.y, extinction.y)]
+            {
+                if choice < channel_weight {
+                    chosen = channel_extinction;
+                    break;
+                }
+                choice -= channel_weight;
+            }
+            let distance = -(1.0 - rng.gen_range(0.0f32, 1.0)).ln() / chosen;
+            if let Some(exit) = self.march_inside(tracer, hit.object, pos, dir, distance) {
+                let survived = transmittance(exit.distance);
+                weight *= survived * weight.sum() / (weight * survived).sum();
+                // The light leaving here came in from outside the object at this point.
+                let normal = self.normal_method.normal_at(object, exit.position);
+                let offset = tracer.inside_offset(&exit);
+                let ray_start = exit.position + normal * offset;
+                let mut result: Vec3 = 0.into();
+                for light in &self.lights {
+                    let sample = light.sample(ray_start);
+                    let to_light = (sample.shadow_ray_target - ray_start).normalized();
+                    let cos = normal.dot(to_light);
+                    if cos > 0.0 {
+                        result += sample.color
+                            * cos
+                            * self.light_reaching(tracer, ray_start, sample.shadow_ray_target);
+                    }
+                }
+                if remaining_bounces > 0 {
+                    let dir = cosine_direction(normal);
+                    result += self.do_camera_ray(
+                        tracer,
+                        ray_start,
+                        dir,
+                        wavelength,
+                        remaining_bounces - 1,
+                    );
+                }
+                return result * weight;
+            }
+            let survived = transmittance(distance);
+            weight *= scattering * survived * weight.sum() / (weight * extinction * survived).sum();
+            pos += dir * distance;
+            dir = Vec3::random_unit_vec();
+            // Same roulette as transmittance_through_media, but on the light carried through
+            // the walk so far.
+            let survival = weight.max_component().min(1.0);
+            if rng.gen_range(0.0, 1.0) >= survival {
+                return 0.into();
+            }
+            weight /= survival;
+        }
+        0.into()
+    }
+
+    /// Returns the light coming towards the viewer from a ray hitting the outside of a
+    /// transparent object, which either reflects off of it or passes through it.
+    fn color_through_surface(
+        &self,
+        tracer: &Tracer,
+        hit: Hit,
+        mat: &MaterialSample,
+        normal: Vec3,
+        wavelength: Option<f32>,
+        mut remaining_bounces: u32,
+    ) -> Vec3 {
+        let offset = tracer.inside_offset(&hit);
+        let ior = mat.ior_at(wavelength);
+        let (mut dir, refracted) = reflect_or_refract(hit.direction, normal, 1.0 / ior);
+        if !refracted {
+            let ray_start = hit.position + normal * offset;
+            return self.do_camera_ray(tracer, ray_start, dir, wavelength, remaining_bounces);
+        }
+        let object = self.objects[hit.object].as_ref();
+        let mut pos = hit.position - normal * offset;
+        let transmittance = |distance: f32| {
+            Vec3::new(
+                (-mat.absorption.x * distance).exp(),
+                (-mat.absorption.y * distance).exp(),
+                (-mat.absorption.z * distance).exp(),
+            )
+        };
+        let mut absorbed: Vec3 = 1.into();
+        loop {
+            let exit = match self.march_inside(tracer, hit.object, pos, dir, self.max_distance) {
+                Some(exit) => exit,
+                // The ray never came back out, like when looking down into the sea, so treat it
+                // like a ray flying off into the sky after going as far as rays can go.
+                None => return self.sky_color * absorbed * transmittance(self.max_distance),
+            };
+            absorbed *= transmittance(exit.distance);
+            let normal = self.normal_method.normal_at(object, exit.position);
+            let offset = tracer.inside_offset(&exit);
+            // The normal points out of the object, so flip it to point back inside towards
+            // where the ray came from.
+            let (new_dir, refracted) = reflect_or_refract(dir, normal * -1, ior);
+            dir = new_dir;
+            if refracted {
+                let ray_start = exit.position + normal * offset;
+                return self.do_camera_ray(tracer, ray_start, dir, wavelength, remaining_bounces)
+                    * absorbed;
+            }
+            // Total internal reflection, or a partial reflection back into the object.
+            if remaining_bounces == 0 {
+                return 0.into();
+            }
+            remaining_bounces -= 1;
+            pos = exit.position - normal * offset;
+        }
+    }
+
+    /// Marches a ray from inside an object until it reaches the object's surface, by treating
+    /// the inside of the object as the outside and vice versa. Other objects are ignored. Returns
+    /// None if the ray runs out of steps or gets max_distance along without reaching the
+    /// surface.
+    fn march_inside(
+        &self,
+        tracer: &Tracer,
+        object: usize,
+        ray_start: Vec3,
+        ray_dir: Vec3,
+        max_distance: f32,
+    ) -> Option<Hit> {
+        let mut travelled = 0.0;
+        for steps in 1..=self.max_steps {
+            let pos = ray_start + ray_dir * travelled;
+            let df = -self.objects[object].distance_to(pos);
+            if df <= tracer.hit_distance_at(travelled, pos) {
+                return Some(Hit {
+                    position: pos,
+                    direction: ray_dir,
+                    object,
+                    distance: travelled,
+                    steps,
+                });
+            }
+            if travelled + df >= max_distance {
+                return None;
+            }
+            travelled += df;
+        }
+        self.max_steps_count.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Returns the light scattered towards where a ray came from by the media at a point.
+    fn color_in_medium(
+        &self,
+        tracer: &Tracer,
+        position: Vec3,
+        direction: Vec3,
+        anisotropy: f32,
+        wavelength: Option<f32>,
+        remaining_bounces: u32,
+    ) -> Vec3 {
+        let mut result: Vec3 = 0.into();
+        for light in &self.lights {
+            let sample = light.sample(position);
+            let to_light = (sample.shadow_ray_target - position).normalized();
+            // Scaled the same way as MaterialSample::reflectance.
+            let phase = henyey_greenstein(direction.dot(to_light), anisotropy) * PI;
+            result += sample.color
+                * phase
+                * self.light_reaching(tracer, position, sample.shadow_ray_target);
+        }
         if remaining_bounces > 0 {
-            let dir = (Vec3::random_unit_vec() + normal).normalized();
-            // This should never be negative.
-            let brightness = dir.dot(normal);
-            let light = self.do_camera_ray(ray_start, dir, remaining_bounces - 1);
-            result += light * surface_color * brightness;
+            let dir = sample_henyey_greenstein(direction, anisotropy);
+            result += self.do_camera_ray(tracer, position, dir, wavelength, remaining_bounces - 1);
         }
         result
     }
 
-    pub fn do_camera_ray(&self, origin: Vec3, direction: Vec3, remaining_bounces: u32) -> Vec3 {
-        match self.march_until_hit(origin, direction) {
-            Some(hit_point) => self.color_on_surface(hit_point, remaining_bounces),
-            None => self.sky_color,
+    /// Returns the light coming back along a ray. When rendering spectrally, wavelength is the
+    /// wavelength of light in nanometers the ray is carrying.
+    pub fn do_camera_ray(
+        &self,
+        tracer: &Tracer,
+        origin: Vec3,
+        direction: Vec3,
+        wavelength: Option<f32>,
+        remaining_bounces: u32,
+    ) -> Vec3 {
+        let hit = match self.march(tracer, origin, direction) {
+            MarchResult::Hit(hit) => Some(hit),
+            MarchResult::Miss => None,
+            MarchResult::OutOfSteps(hit) => match (self.out_of_steps, hit) {
+                (OutOfSteps::Hit, Some(hit)) => Some(hit),
+                (OutOfSteps::Color(color), _) => return color,
+                _ => None,
+            },
+        };
+        let mut weight: Vec3 = 1.into();
+        if !self.media.is_empty() {
+            let length = hit.map_or(self.max_distance, |hit| hit.distance);
+            match track_through_media(&self.media, origin, direction, length) {
+                MediumEvent::Passed(passed) => weight = passed,
+                MediumEvent::Absorbed => return 0.into(),
+                MediumEvent::Scattered {
+                    distance,
+                    weight,
+                    anisotropy,
+                } => {
+                    let position = origin + direction * distance;
+                    return self.color_in_medium(
+                        tracer,
+                        position,
+                        direction,
+                        anisotropy,
+                        wavelength,
+                        remaining_bounces,
+                    ) * weight;
+                }
+            }
         }
+        weight
+            * match hit {
+                Some(hit) => self.color_on_surface(tracer, hit, wavelength, remaining_bounces),
+                None => self.sky_color,
+            }
     }
 }
@@ -0,0 +1,412 @@
+use crate::{Clearcoat, Lobe, MaterialSample, ThinFilm, Vec3};
+use rand::Rng;
+use std::f32::consts::PI;
+use std::sync::OnceLock;
+
+/// Perfectly smooth surfaces make the math blow up, so they are treated as very slightly rough
+/// instead.
+const MIN_ALPHA: f32 = 1e-3;
+
+/// Returns two normalized vectors which are perpendicular to each other and the normal.
+pub(crate) fn tangents(normal: Vec3) -> (Vec3, Vec3) {
+    let (tangent, bitangent) = normal.make_two_perpendicular();
+    (tangent.normalized(), bitangent.normalized())
+}
+
+/// Picks a random direction on the side of the surface the normal points to, preferring
+/// directions close to the normal the way light scattering off a completely rough surface does.
+pub(crate) fn cosine_direction(normal: Vec3) -> Vec3 {
+    let mut rng = rand::thread_rng();
+    let (tangent, bitangent) = tangents(normal);
+    let (u, v): (f32, f32) = (rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+    let around = 2.0 * PI * u;
+    let radius = v.sqrt();
+    tangent * radius * around.cos()
+        + bitangent * radius * around.sin()
+        + normal * (1.0 - v).max(0.0).sqrt()
+}
+
+/// Schlick's approximation of how much light gets reflected instead of entering the surface.
+fn schlick(f0: Vec3, cos_theta: f32) -> Vec3 {
+    // Everything becomes a mirror when seen from a low enough angle, except for materials which
+    // shouldn't reflect anything at all.
+    let f90 = (f0.average() * 50.0).min(1.0);
+    f0 + (Vec3::from(f90) - f0) * (1.0 - cos_theta).max(0.0).powi(5)
+}
+
+/// GGX / Trowbridge-Reitz distribution, describing how many microscopic bumps on the surface
+/// face in the direction of the half vector.
+fn ggx_distribution(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+/// Smith's shadowing term for a single direction, describing how much of the surface is hidden
+/// behind other bumps when seen from that direction.
+fn smith_g1(n_dot_x: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    2.0 * n_dot_x / (n_dot_x + (alpha2 + (1.0 - alpha2) * n_dot_x * n_dot_x).sqrt())
+}
+
+/// How much light gets reflected instead of passing through a smooth boundary between two
+/// transparent materials. eta is the index of refraction on the side the light is coming from
+/// divided by the one on the other side.
+fn dielectric_fresnel(cos_incoming: f32, eta: f32) -> f32 {
+    let sin2_transmitted = eta * eta * (1.0 - cos_incoming * cos_incoming);
+    if sin2_transmitted >= 1.0 {
+        // Total internal reflection.
+        return 1.0;
+    }
+    let cos_transmitted = (1.0 - sin2_transmitted).sqrt();
+    let perpendicular =
+        (eta * cos_incoming - cos_transmitted) / (eta * cos_incoming + cos_transmitted);
+    let parallel = (cos_incoming - eta * cos_transmitted) / (cos_incoming + eta * cos_transmitted);
+    (perpendicular * perpendicular + parallel * parallel) / 2.0
+}
+
+/// Randomly reflects or refracts a ray hitting a smooth transparent surface, with the chance of
+/// reflecting given by the Fresnel equations. normal must point back towards where the ray came
+/// from, and eta is the index of refraction the ray is leaving divided by the one it is
+/// entering. Returns the new direction and whether the ray passed through the surface.
+pub(crate) fn reflect_or_refract(direction: Vec3, normal: Vec3, eta: f32) -> (Vec3, bool) {
+    let cos_incoming = -normal.dot(direction).min(0.0);
+    let reflected = direction + normal * 2.0 * cos_incoming;
+    let fresnel = dielectric_fresnel(cos_incoming, eta);
+    if rand::thread_rng().gen_range(0.0, 1.0) < fresnel {
+        return (reflected, false);
+    }
+    let cos_transmitted = (1.0 - eta * eta * (1.0 - cos_incoming * cos_incoming)).sqrt();
+    let refracted = direction * eta + normal * (eta * cos_incoming - cos_transmitted);
+    (refracted.normalized(), true)
+}
+
+/// Bumps can tilt the shading normal away from the viewer on parts of the surface seen at a low
+/// angle, which would turn them black. This tilts it back just far enough to face the viewer.
+pub(crate) fn facing_viewer(shading_normal: Vec3, to_viewer: Vec3) -> Vec3 {
+    const MIN_COS: f32 = 0.01;
+    let cos = shading_normal.dot(to_viewer);
+    if cos >= MIN_COS {
+        shading_normal
+    } else {
+        (shading_normal + to_viewer * (MIN_COS - cos)).normalized()
+    }
+}
+
+/// Picks the direction of a microscopic bump for light to bounce off of, following the GGX
+/// distribution, and returns the direction the light arrives from to bounce towards to_viewer.
+fn sample_ggx(normal: Vec3, to_viewer: Vec3, alpha: f32) -> Vec3 {
+    let mut rng = rand::thread_rng();
+    let (tangent, bitangent) = tangents(normal);
+    let (u, v): (f32, f32) = (rng.gen_range(0.0, 1.0), rng.gen_range(0.0, 1.0));
+    let around = 2.0 * PI * u;
+    let alpha2 = alpha * alpha;
+    let cos_theta = ((1.0 - v) / (1.0 + (alpha2 - 1.0) * v)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let half = tangent * sin_theta * around.cos()
+        + bitangent * sin_theta * around.sin()
+        + normal * cos_theta;
+    half * 2.0 * to_viewer.dot(half) - to_viewer
+}
+
+/// The chance of sample_ggx picking to_light.
+fn ggx_pdf(normal: Vec3, to_viewer: Vec3, to_light: Vec3, alpha: f32) -> f32 {
+    let half = (to_viewer + to_light).normalized();
+    let n_dot_h = normal.dot(half).max(0.0);
+    let v_dot_h = to_viewer.dot(half).max(1e-6);
+    ggx_distribution(n_dot_h, alpha) * n_dot_h / (4.0 * v_dot_h)
+}
+
+/// Reflection off a GGX surface which reflects all the light hitting it, apart from what gets
+/// hidden by other bumps.
+fn ggx_specular(n_dot_l: f32, n_dot_v: f32, n_dot_h: f32, alpha: f32) -> f32 {
+    ggx_distribution(n_dot_h, alpha) * smith_g1(n_dot_l, alpha) * smith_g1(n_dot_v, alpha)
+        / (4.0 * n_dot_l * n_dot_v)
+}
+
+/// Wavelengths in nanometers used for the red, green and blue parts of thin film interference.
+const FILM_WAVELENGTHS: [f32; 3] = [650.0, 532.0, 450.0];
+
+/// How much light gets reflected by a thin film together with the surface underneath it, where
+/// f0 is how much the surface reflects head on by itself. Assumes the surface has a higher ior
+/// than the film.
+fn thin_film_fresnel(film: ThinFilm, f0: Vec3, cos_theta: f32) -> Vec3 {
+    let eta = 1.0 / film.ior;
+    let sin2_film = eta * eta * (1.0 - cos_theta * cos_theta);
+    if sin2_film >= 1.0 {
+        return 1.into();
+    }
+    let cos_film = (1.0 - sin2_film).sqrt();
+    let top = dielectric_fresnel(cos_theta, eta).sqrt();
+    let bottom = schlick(f0, cos_film);
+    // Reflecting off the top and the bottom of the film both flip the phase of the light, so
+    // the only difference between the two reflections comes from the extra distance travelled
+    // through the film.
+    let interfere = |bottom: f32, wavelength: f32| {
+        let bottom = bottom.sqrt();
+        let phase = 4.0 * PI * film.ior * film.thickness * cos_film / wavelength;
+        let cross = 2.0 * top * bottom * phase.cos();
+        (top * top + bottom * bottom + cross) / (1.0 + top * top * bottom * bottom + cross)
+    };
+    Vec3::new(
+        interfere(bottom.x, FILM_WAVELENGTHS[0]),
+        interfere(bottom.y, FILM_WAVELENGTHS[1]),
+        interfere(bottom.z, FILM_WAVELENGTHS[2]),
+    )
+}
+
+/// The distribution gets too spiky to work with as alpha approaches 0.
+const MIN_SHEEN_ALPHA: f32 = 0.07;
+
+fn sheen_alpha(roughness: f32) -> f32 {
+    (roughness * roughness).max(MIN_SHEEN_ALPHA)
+}
+
+/// Reflection off the fibers of white sheen, using the "Charlie" distribution, which has most
+/// of the fibers lying flat against the surface, and Neubelt and Pettineo's simple stand-in for
+/// the fibers shadowing each other.
+fn sheen_brdf(n_dot_l: f32, n_dot_v: f32, n_dot_h: f32, alpha: f32) -> f32 {
+    let sin2_h = (1.0 - n_dot_h * n_dot_h).max(0.0);
+    let distribution = (2.0 + 1.0 / alpha) * sin2_h.powf(0.5 / alpha) / (2.0 * PI);
+    distribution / (4.0 * (n_dot_l + n_dot_v - n_dot_l * n_dot_v))
+}
+
+/// Returns how much of the light arriving from every direction white sheen reflects towards a
+/// viewer at the given angle. There's no neat formula for this, so it gets added up numerically
+/// the first time it's needed and looked up after that.
+fn sheen_albedo(n_dot_v: f32, roughness: f32) -> f32 {
+    const SIZE: usize = 32;
+    const STEPS: usize = 64;
+    static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = Vec::with_capacity(SIZE * SIZE);
+        for i in 0..SIZE {
+            let alpha = sheen_alpha(i as f32 / (SIZE - 1) as f32);
+            for j in 0..SIZE {
+                let n_dot_v = (j as f32 / (SIZE - 1) as f32).max(1e-3);
+                let to_viewer = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+                let mut total = 0.0;
+                for k in 0..STEPS {
+                    let n_dot_l = (k as f32 + 0.5) / STEPS as f32;
+                    let sin_l = (1.0 - n_dot_l * n_dot_l).sqrt();
+                    for m in 0..STEPS {
+                        let around = 2.0 * PI * (m as f32 + 0.5) / STEPS as f32;
+                        let to_light =
+                            Vec3::new(sin_l * around.cos(), sin_l * around.sin(), n_dot_l);
+                        let half = (to_viewer + to_light).normalized();
+                        total += sheen_brdf(n_dot_l, n_dot_v, half.z, alpha) * n_dot_l;
+                    }
+                }
+                let area = 2.0 * PI / (STEPS * STEPS) as f32;
+                table.push((total * area).min(1.0));
+            }
+        }
+        table
+    });
+    let lookup = |v: f32| {
+        let v = v.clamp(0.0, 1.0) * (SIZE - 1) as f32;
+        let below = (v as usize).min(SIZE - 2);
+        (below, v - below as f32)
+    };
+    let (i, ti) = lookup(roughness);
+    let (j, tj) = lookup(n_dot_v);
+    let at = |i: usize, j: usize| table[i * SIZE + j];
+    let low = at(i, j) * (1.0 - tj) + at(i, j + 1) * tj;
+    let high = at(i + 1, j) * (1.0 - tj) + at(i + 1, j + 1) * tj;
+    low * (1.0 - ti) + high * ti
+}
+
+impl Clearcoat {
+    fn alpha(&self) -> f32 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+}
+
+impl MaterialSample {
+    fn alpha(&self) -> f32 {
+        (self.roughness * self.roughness).max(MIN_ALPHA)
+    }
+
+    /// How much light gets reflected off the surface when looking at it head on.
+    fn f0(&self) -> Vec3 {
+        let dielectric = Vec3::from(0.08 * self.specular);
+        dielectric + (self.base_color - dielectric) * self.metallic
+    }
+
+    /// How much light gets reflected off the surface under the clear coat and sheen.
+    fn fresnel(&self, cos_theta: f32) -> Vec3 {
+        match self.thin_film {
+            Some(film) => thin_film_fresnel(film, self.f0(), cos_theta),
+            None => schlick(self.f0(), cos_theta),
+        }
+    }
+
+    /// How much light makes it through the clear coat, if there is one.
+    fn through_coat(&self, cos_theta: f32) -> f32 {
+        self.clearcoat.map_or(1.0, |coat| {
+            1.0 - coat.amount * dielectric_fresnel(cos_theta, 1.0 / coat.ior)
+        })
+    }
+
+    /// How much light makes it past the sheen, if there is any.
+    fn through_sheen(&self, cos_theta: f32) -> f32 {
+        self.sheen.map_or(1.0, |sheen| {
+            1.0 - sheen.color.max_component().min(1.0) * sheen_albedo(cos_theta, sheen.roughness)
+        })
+    }
+
+    /// Returns how much of the light arriving from to_light gets reflected towards to_viewer,
+    /// including the falloff from light hitting the surface at an angle. A white, completely
+    /// rough surface facing the light reflects all of it. Both directions point away from the
+    /// surface.
+    pub fn reflectance(&self, normal: Vec3, to_viewer: Vec3, to_light: Vec3) -> Vec3 {
+        self.brdf(normal, to_viewer, to_light) * normal.dot(to_light).max(0.0) * PI
+    }
+
+    fn brdf(&self, normal: Vec3, to_viewer: Vec3, to_light: Vec3) -> Vec3 {
+        let n_dot_l = normal.dot(to_light);
+        if n_dot_l <= 0.0 {
+            return 0.into();
+        }
+        let n_dot_v = normal.dot(to_viewer).max(1e-4);
+        let half = (to_viewer + to_light).normalized();
+        let n_dot_h = normal.dot(half).max(0.0);
+        let v_dot_h = to_viewer.dot(half).max(0.0);
+        let specular =
+            self.fresnel(v_dot_h) * ggx_specular(n_dot_l, n_dot_v, n_dot_h, self.alpha());
+        // Light which isn't reflected off the surface enters it, and non-metals scatter some of
+        // that back out in every direction. It has to make it through the surface on the way
+        // in and on the way back out.
+        let transmitted =
+            (Vec3::from(1) - self.fresnel(n_dot_l)) * (Vec3::from(1) - self.fresnel(n_dot_v));
+        let diffuse = self.base_color * self.diffuse_fraction() / PI;
+        let mut result = diffuse * transmitted + specular;
+        // Each layer on top lets through whatever it doesn't reflect, on the way in and out.
+        if let Some(sheen) = self.sheen {
+            let through = self.through_sheen(n_dot_l).min(self.through_sheen(n_dot_v));
+            result = result * through
+                + sheen.color * sheen_brdf(n_dot_l, n_dot_v, n_dot_h, sheen_alpha(sheen.roughness));
+        }
+        if let Some(coat) = self.clearcoat {
+            let alpha = coat.alpha();
+            let reflected = coat.amount
+                * dielectric_fresnel(v_dot_h, 1.0 / coat.ior)
+                * ggx_specular(n_dot_l, n_dot_v, n_dot_h, alpha);
+            result = result * self.through_coat(n_dot_l) * self.through_coat(n_dot_v) + reflected;
+        }
+        result
+    }
+
+    /// How much of the light entering the surface scatters straight back out of it.
+    fn diffuse_fraction(&self) -> f32 {
+        (1.0 - self.metallic) * (1.0 - self.subsurface)
+    }
+
+    /// Returns how much of the light which would reach to_viewer from inside the object gets
+    /// there by wandering around under the surface, rather than scattering straight back out.
+    pub(crate) fn subsurface_fraction(&self, normal: Vec3, to_viewer: Vec3) -> Vec3 {
+        let n_dot_v = normal.dot(to_viewer).max(1e-4);
+        (Vec3::from(1) - self.fresnel(n_dot_v))
+            * (1.0 - self.metallic)
+            * self.subsurface
+            * self.through_sheen(n_dot_v)
+            * self.through_coat(n_dot_v)
+    }
+
+    /// Chance of picking each lobe, in the order Diffuse, Specular, Clearcoat, Sheen and
+    /// Subsurface, based on roughly how much light each one reflects towards to_viewer.
+    fn lobe_chances(&self, normal: Vec3, to_viewer: Vec3) -> [f32; 5] {
+        let n_dot_v = normal.dot(to_viewer).max(1e-4);
+        let under_coat = self.through_coat(n_dot_v);
+        let under_sheen = under_coat * self.through_sheen(n_dot_v);
+        let specular = self.fresnel(n_dot_v).average();
+        let mut chances = [
+            under_sheen * self.base_color.average() * self.diffuse_fraction() * (1.0 - specular),
+            under_sheen * specular,
+            self.clearcoat.map_or(0.0, |coat| {
+                coat.amount * dielectric_fresnel(n_dot_v, 1.0 / coat.ior)
+            }),
+            self.sheen.map_or(0.0, |sheen| {
+                under_coat * sheen.color.average() * sheen_albedo(n_dot_v, sheen.roughness)
+            }),
+            self.subsurface_fraction(normal, to_viewer).average(),
+        ];
+        let total: f32 = chances.iter().sum();
+        if total > 0.0 {
+            for chance in &mut chances {
+                *chance /= total;
+            }
+        }
+        chances
+    }
+
+    /// Randomly picks which way light bounces towards to_viewer, preferring lobes which reflect
+    /// more light. Returns the lobe along with the chance of picking it, or None if the surface
+    /// doesn't reflect anything.
+    pub fn pick_lobe(&self, normal: Vec3, to_viewer: Vec3) -> Option<(Lobe, f32)> {
+        const LOBES: [Lobe; 5] = [
+            Lobe::Diffuse,
+            Lobe::Specular,
+            Lobe::Clearcoat,
+            Lobe::Sheen,
+            Lobe::Subsurface,
+        ];
+        let chances = self.lobe_chances(normal, to_viewer);
+        let mut choice = rand::thread_rng().gen_range(0.0, 1.0);
+        for (&lobe, &chance) in LOBES.iter().zip(&chances) {
+            if chance > 0.0 && choice < chance {
+                return Some((lobe, chance));
+            }
+            choice -= chance;
+        }
+        // Rounding can leave a tiny bit of room after the last lobe.
+        LOBES
+            .iter()
+            .zip(&chances)
+            .rev()
+            .find(|&(_, &chance)| chance > 0.0)
+            .map(|(&lobe, &chance)| (lobe, chance))
+    }
+
+    /// The chance of sample_bounce picking to_light, out of every lobe pick_lobe could pick.
+    fn pdf(&self, normal: Vec3, to_viewer: Vec3, to_light: Vec3) -> f32 {
+        let [diffuse, specular, clearcoat, sheen, _] = self.lobe_chances(normal, to_viewer);
+        let cosine = normal.dot(to_light).max(0.0) / PI;
+        let mut pdf = (diffuse + sheen) * cosine
+            + specular * ggx_pdf(normal, to_viewer, to_light, self.alpha());
+        if let Some(coat) = self.clearcoat {
+            let alpha = coat.alpha();
+            pdf += clearcoat * ggx_pdf(normal, to_viewer, to_light, alpha);
+        }
+        pdf
+    }
+
+    /// Picks a random direction for light to arrive from before bouncing towards to_viewer off
+    /// the given lobe, preferring directions which contribute more. Returns the direction along
+    /// with how much the light coming from it should be multiplied by, or None if the light gets
+    /// absorbed. The weight accounts for pick_lobe's choice, so the lobe should come from there.
+    pub fn sample_bounce(&self, lobe: Lobe, normal: Vec3, to_viewer: Vec3) -> Option<(Vec3, Vec3)> {
+        let to_light = match lobe {
+            // Sheen spreads light widely enough that the diffuse directions suit it fine.
+            Lobe::Diffuse | Lobe::Sheen => cosine_direction(normal),
+            Lobe::Specular => sample_ggx(normal, to_viewer, self.alpha()),
+            Lobe::Clearcoat => {
+                let coat = self.clearcoat?;
+                sample_ggx(normal, to_viewer, coat.alpha())
+            }
+            Lobe::Subsurface => return None,
+        };
+        let n_dot_l = normal.dot(to_light);
+        if n_dot_l <= 0.0 {
+            return None;
+        }
+        let pdf = self.pdf(normal, to_viewer, to_light);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some((
+            to_light,
+            self.brdf(normal, to_viewer, to_light) * n_dot_l / pdf,
+        ))
+    }
+}
@@ -0,0 +1,109 @@
+use crate::Vec3;
+use std::sync::OnceLock;
+
+/// Shortest wavelength of visible light in nanometers.
+pub const MIN_WAVELENGTH: f32 = 380.0;
+/// Longest wavelength of visible light in nanometers.
+pub const MAX_WAVELENGTH: f32 = 780.0;
+/// The wavelength in nanometers that indexes of refraction are usually measured at.
+pub const REFERENCE_WAVELENGTH: f32 = 589.3;
+
+/// Describes how the index of refraction of a transparent material changes with the wavelength
+/// of light, which splits white light into a rainbow. Only has an effect when rendering with
+/// Renderer::spectral turned on.
+#[derive(Clone, Copy, Debug)]
+pub enum Dispersion {
+    /// Every wavelength bends by the same amount.
+    None,
+    /// Cauchy's equation, where the index of refraction goes up by this coefficient divided by
+    /// the square of the wavelength in micrometers. The material's ior is used for yellow light
+    /// at 589.3nm. About 0.004 for crown glass and 0.013 for flint glass.
+    Cauchy(f32),
+    /// The Sellmeier equation, which is more accurate than Cauchy's equation but needs
+    /// measured coefficients. C is in square micrometers. The material's ior is ignored.
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    /// Borosilicate crown glass, the most common glass for lenses.
+    pub const BK7: Dispersion = Dispersion::Sellmeier {
+        b: [1.039_612, 0.231_792_3, 1.010_469],
+        c: [0.006_000_7, 0.020_017_9, 103.560_65],
+    };
+    pub const DIAMOND: Dispersion = Dispersion::Sellmeier {
+        b: [0.3306, 4.3356, 0.0],
+        c: [0.030_625, 0.011_236, 0.0],
+    };
+
+    /// Returns the index of refraction at the given wavelength in nanometers, given the index of
+    /// refraction for yellow light.
+    pub fn ior_at(&self, ior: f32, wavelength: f32) -> f32 {
+        let micrometers = wavelength / 1000.0;
+        match self {
+            Self::None => ior,
+            Self::Cauchy(coefficient) => {
+                let reference = REFERENCE_WAVELENGTH / 1000.0;
+                ior + coefficient / (micrometers * micrometers)
+                    - coefficient / (reference * reference)
+            }
+            Self::Sellmeier { b, c } => {
+                let l2 = micrometers * micrometers;
+                let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f32>();
+                n2.sqrt()
+            }
+        }
+    }
+}
+
+/// One lobe of the fit to the CIE color matching functions, with a different width on either
+/// side of its center.
+fn lobe(wavelength: f32, center: f32, width_below: f32, width_above: f32) -> f32 {
+    let width = if wavelength < center {
+        width_below
+    } else {
+        width_above
+    };
+    let t = (wavelength - center) / width;
+    (-0.5 * t * t).exp()
+}
+
+/// How strongly a single wavelength in nanometers excites each of the X, Y and Z channels of the
+/// CIE 1931 standard observer, using the fit from "Simple Analytic Approximations to the CIE XYZ
+/// Color Matching Functions" by Wyman, Sloan and Shirley.
+pub fn wavelength_to_xyz(wavelength: f32) -> Vec3 {
+    let l = wavelength;
+    Vec3::new(
+        1.056 * lobe(l, 599.8, 37.9, 31.0) + 0.362 * lobe(l, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(l, 501.1, 20.4, 26.2),
+        0.821 * lobe(l, 568.8, 46.9, 40.5) + 0.286 * lobe(l, 530.9, 16.3, 31.1),
+        1.217 * lobe(l, 437.0, 11.8, 36.0) + 0.681 * lobe(l, 459.0, 26.0, 13.8),
+    )
+}
+
+/// Converts CIE XYZ to linear sRGB.
+fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    Vec3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// Returns the color of a single wavelength in nanometers, scaled so that averaging it over
+/// every visible wavelength gives exactly white. Some wavelengths are more saturated than any
+/// RGB color, so the result can have negative components.
+pub fn wavelength_to_rgb(wavelength: f32) -> Vec3 {
+    static WHITE: OnceLock<Vec3> = OnceLock::new();
+    let white = WHITE.get_or_init(|| {
+        const STEPS: u32 = 1000;
+        let mut total: Vec3 = 0.into();
+        for step in 0..STEPS {
+            let t = (step as f32 + 0.5) / STEPS as f32;
+            total += xyz_to_rgb(wavelength_to_xyz(
+                MIN_WAVELENGTH + t * (MAX_WAVELENGTH - MIN_WAVELENGTH),
+            ));
+        }
+        total / STEPS as f32
+    });
+    xyz_to_rgb(wavelength_to_xyz(wavelength)) / *white
+}
@@ -0,0 +1,223 @@
+use crate::{Material, MaterialSample, Vec3};
+
+/// A value which changes depending on where it is looked up. Textures are looked up by the
+/// position of a point on the surface and the surface normal there instead of UV coordinates.
+/// Solid textures only use the position, while image textures use both to work out which part
+/// of the image to show. Textures which only produce a single number return it in all three
+/// components.
+pub trait Texture: Send + Sync {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3;
+
+    /// Blends from this texture to the other one as factor goes from 0 to 1.
+    fn mix<B: Texture, F: Texture>(self, other: B, factor: F) -> Mix<Self, B, F>
+    where
+        Self: Sized,
+    {
+        Mix {
+            a: self,
+            b: other,
+            factor,
+        }
+    }
+
+    /// Linearly maps values between from_min and from_max to values between to_min and to_max.
+    /// Values outside the range are clamped. If from_min and from_max are the same, every value
+    /// maps to to_min.
+    fn remap(self, from_min: f32, from_max: f32, to_min: f32, to_max: f32) -> Remap<Self>
+    where
+        Self: Sized,
+    {
+        Remap {
+            texture: self,
+            from_min,
+            from_max,
+            to_min,
+            to_max,
+        }
+    }
+
+    fn multiply<B: Texture>(self, other: B) -> Multiply<Self, B>
+    where
+        Self: Sized,
+    {
+        Multiply { a: self, b: other }
+    }
+}
+
+impl Texture for Vec3 {
+    fn value_at(&self, _pos: Vec3, _normal: Vec3) -> Vec3 {
+        *self
+    }
+}
+
+impl Texture for f32 {
+    fn value_at(&self, _pos: Vec3, _normal: Vec3) -> Vec3 {
+        (*self).into()
+    }
+}
+
+impl<F: Fn(Vec3) -> Vec3 + Send + Sync> Texture for F {
+    fn value_at(&self, pos: Vec3, _normal: Vec3) -> Vec3 {
+        self(pos)
+    }
+}
+
+pub struct Mix<A: Texture, B: Texture, F: Texture> {
+    a: A,
+    b: B,
+    factor: F,
+}
+
+impl<A: Texture, B: Texture, F: Texture> Texture for Mix<A, B, F> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        let a = self.a.value_at(pos, normal);
+        a + (self.b.value_at(pos, normal) - a) * self.factor.value_at(pos, normal)
+    }
+}
+
+pub struct Remap<T: Texture> {
+    texture: T,
+    from_min: f32,
+    from_max: f32,
+    to_min: f32,
+    to_max: f32,
+}
+
+impl<T: Texture> Texture for Remap<T> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        let width = self.from_max - self.from_min;
+        if width == 0.0 {
+            return self.to_min.into();
+        }
+        let t = (self.texture.value_at(pos, normal) - self.from_min) / width;
+        Vec3::from(self.to_min) + t.saturated() * (self.to_max - self.to_min)
+    }
+}
+
+pub struct Multiply<A: Texture, B: Texture> {
+    a: A,
+    b: B,
+}
+
+impl<A: Texture, B: Texture> Texture for Multiply<A, B> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        self.a.value_at(pos, normal) * self.b.value_at(pos, normal)
+    }
+}
+
+/// A 3D checkerboard made of cubes of the given size, alternating between a and b.
+#[derive(Clone, Debug)]
+pub struct Checker<A: Texture, B: Texture> {
+    pub a: A,
+    pub b: B,
+    pub size: f32,
+}
+
+impl<A: Texture, B: Texture> Texture for Checker<A, B> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        let cell = |v: f32| (v / self.size).floor() as i64;
+        if (cell(pos.x) + cell(pos.y) + cell(pos.z)).rem_euclid(2) == 0 {
+            self.a.value_at(pos, normal)
+        } else {
+            self.b.value_at(pos, normal)
+        }
+    }
+}
+
+/// Stripes of the given width alternating between a and b along the given direction.
+#[derive(Clone, Debug)]
+pub struct Stripes<A: Texture, B: Texture> {
+    pub a: A,
+    pub b: B,
+    pub direction: Vec3,
+    pub width: f32,
+}
+
+impl<A: Texture, B: Texture> Texture for Stripes<A, B> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        let along = pos.dot(self.direction.normalized()) / self.width;
+        if (along.floor() as i64).rem_euclid(2) == 0 {
+            self.a.value_at(pos, normal)
+        } else {
+            self.b.value_at(pos, normal)
+        }
+    }
+}
+
+/// Blends from a at start to b at end. Points past either end get the value at that end.
+#[derive(Clone, Debug)]
+pub struct Gradient<A: Texture, B: Texture> {
+    pub a: A,
+    pub b: B,
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl<A: Texture, B: Texture> Texture for Gradient<A, B> {
+    fn value_at(&self, pos: Vec3, normal: Vec3) -> Vec3 {
+        let axis = self.end - self.start;
+        let t = ((pos - self.start).dot(axis) / axis.dot(axis)).clamp(0.0, 1.0);
+        let a = self.a.value_at(pos, normal);
+        a + (self.b.value_at(pos, normal) - a) * t
+    }
+}
+
+/// Which part of a material a texture controls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    BaseColor,
+    Emission,
+    Roughness,
+    Metallic,
+    Specular,
+    Transmission,
+    Ior,
+    Absorption,
+    Subsurface,
+    SubsurfaceAlbedo,
+    MeanFreePath,
+}
+
+/// Replaces one channel of a material with the value of a texture. Channels which are a single
+/// number use the average of the texture's components.
+pub struct Textured<M: Material, T: Texture> {
+    pub(crate) material: M,
+    pub(crate) channel: Channel,
+    pub(crate) texture: T,
+}
+
+impl<M: Material, T: Texture> Material for Textured<M, T> {
+    fn sample(&self, pos: Vec3, normal: Vec3) -> MaterialSample {
+        let mut sample = self.material.sample(pos, normal);
+        let value = self.texture.value_at(pos, normal);
+        let number = value.average();
+        match self.channel {
+            Channel::BaseColor => sample.base_color = value,
+            Channel::Emission => sample.emission = value,
+            Channel::Roughness => sample.roughness = number,
+            Channel::Metallic => sample.metallic = number,
+            Channel::Specular => sample.specular = number,
+            Channel::Transmission => sample.transmission = number,
+            Channel::Ior => sample.ior = number,
+            Channel::Absorption => sample.absorption = value,
+            Channel::Subsurface => sample.subsurface = number,
+            Channel::SubsurfaceAlbedo => sample.subsurface_albedo = value,
+            Channel::MeanFreePath => sample.mean_free_path = value,
+        }
+        sample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remap() {
+        let at = |texture: &dyn Texture| texture.value_at(0.into(), (0, 1, 0).into()).x;
+        assert_eq!(at(&0.5.remap(0.0, 2.0, 1.0, 3.0)), 1.5);
+        assert_eq!(at(&5.0.remap(0.0, 2.0, 1.0, 3.0)), 3.0);
+        assert_eq!(at(&0.5.remap(1.0, 1.0, 2.0, 3.0)), 2.0);
+        assert_eq!(at(&1.0.remap(1.0, 1.0, 2.0, 3.0)), 2.0);
+    }
+}
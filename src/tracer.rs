@@ -0,0 +1,58 @@
+use crate::{Camera, Hit, Vec3, MIN_SDF_DISTANCE};
+
+/// Controls how rays step through the scene and how close they need to get to a surface to count
+/// as hitting it.
+#[derive(Clone, Copy, Debug)]
+pub struct Tracer {
+    /// Each step goes this many times further than the distance field says is safe, which takes
+    /// fewer steps when rays run alongside surfaces. Values between 1 and 2 make sense. Whenever
+    /// a step might have jumped over a surface, the ray goes back and continues with regular
+    /// steps instead.
+    pub relaxation: f32,
+    /// Rays hit a surface when they get this close to it...
+    pub hit_distance: f32,
+    /// ...plus this much for every unit of distance they have travelled. Far away surfaces only
+    /// cover a small part of a pixel, so there is no point tracing them as precisely as close
+    /// ones.
+    pub hit_distance_growth: f32,
+}
+
+impl Default for Tracer {
+    /// Plain sphere tracing with a fixed hit distance.
+    fn default() -> Self {
+        Self {
+            relaxation: 1.0,
+            hit_distance: MIN_SDF_DISTANCE,
+            hit_distance_growth: 0.0,
+        }
+    }
+}
+
+impl Tracer {
+    /// Over-relaxed tracing where the hit distance is a fraction of how big a pixel is at the
+    /// point being checked.
+    pub fn adaptive(camera: &Camera) -> Self {
+        const PIXEL_FRACTION: f32 = 0.25;
+        let (size, growth) = camera.pixel_footprint();
+        Self {
+            relaxation: 1.6,
+            hit_distance: (size * PIXEL_FRACTION).max(MIN_SDF_DISTANCE),
+            hit_distance_growth: growth * PIXEL_FRACTION,
+        }
+    }
+
+    /// How close a ray which has travelled the given distance and is at the given position needs
+    /// to be to a surface to hit it.
+    pub fn hit_distance_at(&self, travelled: f32, position: Vec3) -> f32 {
+        // Far from the origin, positions can't be stored precisely enough to get any closer.
+        let precision = position.abs().max_component() * f32::EPSILON * 4.0;
+        (self.hit_distance + self.hit_distance_growth * travelled).max(precision)
+    }
+
+    /// How far to move away from a hit on the surface of an object before marching into or out
+    /// of it. Points found while marching inside the object can be on either side of the
+    /// surface, so this is further than usual.
+    pub(crate) fn inside_offset(&self, hit: &Hit) -> f32 {
+        self.hit_distance_at(hit.distance, hit.position) * 4.0
+    }
+}
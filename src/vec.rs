@@ -41,9 +41,9 @@ impl Vec3 {
     pub fn cross<T: Into<Self>>(self, other: T) -> Self {
         let other = other.into();
         Self {
-            x: self.y * other.z,
-            y: self.z * other.x,
-            z: self.x * other.y,
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
         }
     }
 
@@ -71,6 +71,8 @@ impl Vec3 {
         self / self.magnitude()
     }
 
+    // Unlike clamp, this turns NaN into 0.
+    #[allow(clippy::manual_clamp)]
     pub fn saturated(self) -> Self {
         Self {
             x: self.x.max(0.0).min(1.0),
@@ -86,6 +88,26 @@ impl Vec3 {
     pub fn sum(self) -> f32 {
         self.x + self.y + self.z
     }
+
+    pub fn average(self) -> f32 {
+        self.sum() / 3.0
+    }
+
+    /// Returns the bigger value of each component.
+    pub fn max<T: Into<Self>>(self, other: T) -> Self {
+        let other = other.into();
+        (self.x.max(other.x), self.y.max(other.y), self.z.max(other.z)).into()
+    }
+
+    /// Returns the smaller value of each component.
+    pub fn min<T: Into<Self>>(self, other: T) -> Self {
+        let other = other.into();
+        (self.x.min(other.x), self.y.min(other.y), self.z.min(other.z)).into()
+    }
+
+    pub fn max_component(self) -> f32 {
+        self.x.max(self.y).max(self.z)
+    }
 }
 
 impl<T: NumCast + Copy> From<[T; 3]> for Vec3 {